@@ -0,0 +1,443 @@
+use anyhow::{anyhow, Result};
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::error::{BadHit, Loc, PakxError};
+use crate::util::{parse_int, parse_int_type, read_input, try_pack, try_unpack, write_bytes_to, Endian, InFmt, OutFmt, Width, MAX_OUTPUT};
+
+/// Receives the `PakxError::BadChars` report for output written in warn mode.
+#[derive(Clone)]
+pub struct WarnHook(Arc<dyn Fn(&PakxError) + Send + Sync>);
+
+impl std::fmt::Debug for WarnHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WarnHook")
+    }
+}
+
+impl PartialEq for WarnHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for WarnHook {}
+
+/// Bytes that must not appear in output, e.g. `00 0a 0d 20` for shellcode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadChars {
+    set: [u64; 4], // one bit per byte value
+    warn: bool,
+    on_warning: Option<WarnHook>,
+}
+
+impl BadChars {
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut set = [0u64; 4];
+        for &b in bytes {
+            set[b as usize / 64] |= 1 << (b % 64);
+        }
+        Self { set, warn: false, on_warning: None }
+    }
+
+    /// Let offending output through instead of refusing it; the hits go to
+    /// the `on_warning` hook, or nowhere if none is set.
+    pub fn warn(mut self, yes: bool) -> Self {
+        self.warn = yes;
+        self
+    }
+
+    pub fn on_warning(mut self, hook: impl Fn(&PakxError) + Send + Sync + 'static) -> Self {
+        self.on_warning = Some(WarnHook(Arc::new(hook)));
+        self
+    }
+
+    pub fn contains(&self, b: u8) -> bool {
+        self.set[b as usize / 64] & (1 << (b % 64)) != 0
+    }
+
+    /// Every offending byte, labelled by `origin` where it knows the source.
+    pub fn find(&self, data: &[u8], origin: &dyn Fn(usize) -> Option<String>) -> Vec<BadHit> {
+        data.iter()
+            .enumerate()
+            .filter(|&(_, &b)| self.contains(b))
+            .map(|(offset, &byte)| BadHit { offset, byte, origin: origin(offset) })
+            .collect()
+    }
+
+    /// Fail with `PakxError::BadChars` if `data` contains a bad byte, or in
+    /// warn mode pass the report to the hook and succeed.
+    pub fn check(&self, data: &[u8], origin: &dyn Fn(usize) -> Option<String>) -> Result<(), PakxError> {
+        let hits = self.find(data, origin);
+        if hits.is_empty() {
+            return Ok(());
+        }
+        let err = PakxError::BadChars { hits };
+        if !self.warn {
+            return Err(err);
+        }
+        if let Some(WarnHook(hook)) = &self.on_warning {
+            hook(&err);
+        }
+        Ok(())
+    }
+}
+
+/// Renders byte buffers as raw bytes, hex, C or Python literals.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Formatter {
+    outfmt: OutFmt,
+    sep: String,
+    uppercase: bool,
+    badchars: Option<BadChars>,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new(OutFmt::default())
+    }
+}
+
+impl Formatter {
+    pub fn new(outfmt: OutFmt) -> Self {
+        Self { outfmt, sep: " ".into(), uppercase: false, badchars: None }
+    }
+
+    /// Separator between bytes in hex output.
+    pub fn sep(mut self, sep: impl Into<String>) -> Self {
+        self.sep = sep.into();
+        self
+    }
+
+    pub fn uppercase(mut self, yes: bool) -> Self {
+        self.uppercase = yes;
+        self
+    }
+
+    /// Check output against a bad-character set before writing it.
+    pub fn badchars(mut self, badchars: Option<BadChars>) -> Self {
+        self.badchars = badchars;
+        self
+    }
+
+    /// Whether `b` may appear in output under the bad-character set.
+    pub fn allows(&self, b: u8) -> bool {
+        self.badchars.as_ref().is_none_or(|bc| !bc.contains(b))
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W, data: &[u8]) -> Result<()> {
+        self.write_traced(w, data, &|_| None)
+    }
+
+    /// Like `write`, with `origin` naming the input that produced each offset.
+    ///
+    /// Bad characters fail with `PakxError::BadChars`, unless the set is in
+    /// warn mode; see `BadChars::check`.
+    pub fn write_traced<W: Write>(&self, w: &mut W, data: &[u8], origin: &dyn Fn(usize) -> Option<String>) -> Result<()> {
+        if let Some(bc) = &self.badchars {
+            bc.check(data, origin)?;
+        }
+        write_bytes_to(w, self.outfmt, data, &self.sep, self.uppercase)
+    }
+
+    /// Format into an owned buffer instead of a writer.
+    pub fn format(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        self.write(&mut out, data)?;
+        Ok(out)
+    }
+}
+
+/// One value to pack, optionally with its own type as in `u16:0x10`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Item {
+    pub value: i128,
+    /// Width and signedness overriding the packer's defaults.
+    pub ty: Option<(Width, bool)>,
+}
+
+impl From<i128> for Item {
+    fn from(value: i128) -> Self {
+        Item { value, ty: None }
+    }
+}
+
+/// Parse `VALUE` or `TYPE:VALUE`, where TYPE is `u8`..`u128` or `i8`..`i128`.
+pub fn parse_item(s: &str) -> Result<Item, PakxError> {
+    if let Some((t, v)) = s.split_once(':') {
+        if let Some(ty) = parse_int_type(t.trim()) {
+            return Ok(Item { value: parse_int(v)?, ty: Some(ty) });
+        }
+    }
+    Ok(Item { value: parse_int(s)?, ty: None })
+}
+
+/// A packed buffer and where each part of it came from.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Packed {
+    pub bytes: Vec<u8>,
+    /// Byte range of every packed value and the index of the input it came from.
+    pub values: Vec<(usize, Range<usize>)>,
+    /// Byte ranges inserted for alignment.
+    pub padding: Vec<Range<usize>>,
+}
+
+impl Packed {
+    fn pad_to(&mut self, align: usize, byte: u8) -> Result<()> {
+        let len = self.bytes.len();
+        let target = len
+            .div_ceil(align)
+            .checked_mul(align)
+            .filter(|&n| n <= MAX_OUTPUT.max(len))
+            .ok_or_else(|| anyhow!("aligning {len} bytes to {align} exceeds the {MAX_OUTPUT}-byte limit"))?;
+        if target > len {
+            self.bytes.resize(target, byte);
+            self.padding.push(len..target);
+        }
+        Ok(())
+    }
+
+    /// Repeat the whole buffer until it is exactly `len` bytes long; the last
+    /// copy may be partial.
+    fn cycle_to(&mut self, len: usize) -> Result<()> {
+        let n = self.bytes.len();
+        if n == 0 && len > 0 {
+            return Err(anyhow!("nothing to repeat: --fill-to needs at least one value"));
+        }
+        if len > MAX_OUTPUT {
+            return Err(anyhow!("--fill-to {len} exceeds the {MAX_OUTPUT}-byte limit"));
+        }
+        let (values, padding) = (self.values.clone(), self.padding.clone());
+        while self.bytes.len() < len {
+            let base = self.bytes.len();
+            self.bytes.extend_from_within(..n);
+            self.values.extend(values.iter().map(|(i, r)| (*i, r.start + base..r.end + base)));
+            self.padding.extend(padding.iter().map(|r| r.start + base..r.end + base));
+        }
+        self.truncate(len);
+        Ok(())
+    }
+
+    /// Cut the buffer to at most `len` bytes, clipping the layout with it.
+    fn truncate(&mut self, len: usize) {
+        self.bytes.truncate(len);
+        self.values.retain(|(_, r)| r.start < len);
+        self.padding.retain(|r| r.start < len);
+        for r in self.values.iter_mut().map(|(_, r)| r).chain(self.padding.iter_mut()) {
+            r.end = r.end.min(len);
+        }
+    }
+}
+
+/// Packs integers into a contiguous byte buffer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Packer {
+    width: Width,
+    endian: Endian,
+    signed: bool,
+    strict: bool,
+    repeat: Option<usize>,
+    natural: bool,
+    align: Option<usize>,
+    pad_byte: u8,
+    fill_to: Option<usize>,
+    max_len: Option<usize>,
+}
+
+impl Packer {
+    /// `width` applies to values without a `TYPE:` prefix.
+    pub fn new(width: Width) -> Self {
+        Self {
+            width,
+            endian: Endian::Little,
+            signed: false,
+            strict: false,
+            repeat: None,
+            natural: false,
+            align: None,
+            pad_byte: 0,
+            fill_to: None,
+            max_len: None,
+        }
+    }
+
+    pub fn width(&self) -> Width {
+        self.width
+    }
+
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    pub fn signed(mut self, yes: bool) -> Self {
+        self.signed = yes;
+        self
+    }
+
+    /// Reject values that do not fit instead of masking them.
+    pub fn strict(mut self, yes: bool) -> Self {
+        self.strict = yes;
+        self
+    }
+
+    /// Repeat the packed values `times` times in total.
+    pub fn repeat(mut self, times: Option<usize>) -> Self {
+        self.repeat = times;
+        self
+    }
+
+    /// Pad before each value so it starts on a multiple of its own size.
+    pub fn natural(mut self, yes: bool) -> Self {
+        self.natural = yes;
+        self
+    }
+
+    /// Pad the end of the buffer to a multiple of `align` bytes.
+    pub fn align(mut self, align: Option<usize>) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn pad_byte(mut self, byte: u8) -> Self {
+        self.pad_byte = byte;
+        self
+    }
+
+    /// Repeat the packed buffer cyclically to exactly `len` bytes.
+    pub fn fill_to(mut self, len: Option<usize>) -> Self {
+        self.fill_to = len;
+        self
+    }
+
+    /// Cut the output to at most `len` bytes, after every other step.
+    pub fn max_len(mut self, len: Option<usize>) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    pub fn pack_items(&self, items: &[Item]) -> Result<Packed> {
+        self.pack_located(items, &Loc::index)
+    }
+
+    /// `pack_items`, with `locate` giving the position reported for item `i`.
+    fn pack_located(&self, items: &[Item], locate: &dyn Fn(usize) -> Loc) -> Result<Packed> {
+        if self.align == Some(0) {
+            return Err(anyhow!("alignment must be at least 1"));
+        }
+        let mut out = Packed::default();
+        let times = if items.is_empty() { 1 } else { self.repeat.unwrap_or(1).max(1) };
+
+        for _ in 0..times {
+            for (i, item) in items.iter().enumerate() {
+                let (width, signed) = item.ty.unwrap_or((self.width, self.signed));
+                if self.natural {
+                    out.pad_to(width.bytes(), self.pad_byte)?;
+                }
+                let b = try_pack(item.value, width, self.endian, signed, self.strict)
+                    .map_err(|e| e.at(locate(i)))?;
+                let start = out.bytes.len();
+                out.bytes.extend_from_slice(&b);
+                out.values.push((i, start..out.bytes.len()));
+            }
+        }
+
+        if let Some(n) = self.align {
+            out.pad_to(n, self.pad_byte)?;
+        }
+        if let Some(len) = self.fill_to {
+            out.cycle_to(len)?;
+        }
+        if let Some(len) = self.max_len {
+            out.truncate(len);
+        }
+        Ok(out)
+    }
+
+    pub fn pack_ints(&self, values: &[i128]) -> Result<Vec<u8>> {
+        let items: Vec<Item> = values.iter().map(|&n| n.into()).collect();
+        Ok(self.pack_items(&items)?.bytes)
+    }
+
+    /// Parse each value with `parse_item` and pack the result, keeping the layout.
+    pub fn pack_layout<S: AsRef<str>>(&self, values: &[S]) -> Result<Packed> {
+        let items = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| parse_item(v.as_ref()).map_err(|e| e.at(Loc::in_list(values, i))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.pack_located(&items, &|i| Loc::in_list(values, i))
+    }
+
+    /// Parse each value with `parse_item` and pack the result.
+    pub fn pack<S: AsRef<str>>(&self, values: &[S]) -> Result<Vec<u8>> {
+        Ok(self.pack_layout(values)?.bytes)
+    }
+}
+
+/// Decodes a byte stream into integers of one width.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unpacker {
+    width: Width,
+    endian: Endian,
+    signed: bool,
+    count: Option<usize>,
+    input: InFmt,
+}
+
+impl Unpacker {
+    pub fn new(width: Width) -> Self {
+        Self { width, endian: Endian::Little, signed: false, count: None, input: InFmt::Raw }
+    }
+
+    pub fn width(&self) -> Width {
+        self.width
+    }
+
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    pub fn signed(mut self, yes: bool) -> Self {
+        self.signed = yes;
+        self
+    }
+
+    /// Stop after `count` values.
+    pub fn count(mut self, count: Option<usize>) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// How `read`/`run` interpret their input stream.
+    pub fn input(mut self, input: InFmt) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Decode whole words from `data`; a trailing partial word is ignored.
+    pub fn unpack(&self, data: &[u8]) -> Result<Vec<i128>, PakxError> {
+        let limit = self.count.unwrap_or(usize::MAX);
+
+        data.chunks_exact(self.width.bytes())
+            .take(limit)
+            .map(|chunk| try_unpack(chunk, self.width, self.endian, self.signed))
+            .collect()
+    }
+
+    pub fn read<R: Read>(&self, r: R) -> Result<Vec<i128>> {
+        let data = read_input(r, self.input)?;
+        Ok(self.unpack(&data)?)
+    }
+
+    /// Read from `r` and write one decimal value per line to `w`.
+    pub fn run<R: Read, W: Write>(&self, r: R, w: &mut W) -> Result<()> {
+        for v in self.read(r)? {
+            writeln!(w, "{v}")?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
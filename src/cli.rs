@@ -0,0 +1,109 @@
+//! Command-line argument types shared by the subcommands in `cmd`.
+
+use clap::{Args, ValueEnum};
+use crate::api::BadChars;
+use crate::util::{pack_scalar, parse_hex_str, parse_int, Endian, InFmt, OutFmt, Width};
+
+#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum OutFmtArg { Raw, Hex, C, Py }
+#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum InFmtArg { Raw, Hex }
+
+#[derive(Args)]
+pub struct BadcharArgs {
+    /// Refuse output containing any of these bytes, e.g. "00 0a 0d 20".
+    #[arg(long, value_parser = parse_badchars)]
+    pub badchars: Option<BadChars>,
+    /// Only warn on stderr about bad bytes instead of failing.
+    #[arg(long, requires = "badchars")]
+    pub badchars_warn: bool,
+}
+
+#[derive(Args)]
+pub struct ByteIoArgs {
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+}
+
+/// A single value given as --p8 .. --p128, packed like `pN VALUE`.
+#[derive(Args)]
+pub struct PackedValueArgs {
+    #[arg(long, allow_hyphen_values = true)]
+    pub p8: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub p16: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub p32: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub p64: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub p128: Option<String>,
+    #[arg(long)]
+    pub signed: bool,
+}
+
+impl PackedValueArgs {
+    /// The --pN value packed with `endian`, or None when no --pN was given.
+    pub fn packed(&self, endian: Endian, strict: bool) -> anyhow::Result<Option<Vec<u8>>> {
+        let values = [(8, &self.p8), (16, &self.p16), (32, &self.p32), (64, &self.p64), (128, &self.p128)];
+        let Some((bits, Some(s))) = values.into_iter().find(|(_, s)| s.is_some()) else {
+            return Ok(None);
+        };
+        Ok(Some(pack_scalar(parse_int(s)?, bits, endian, self.signed, strict)?))
+    }
+}
+
+pub fn endian_from(be: bool, _le: bool) -> Endian {
+    if be { Endian::Big } else { Endian::Little }
+}
+
+pub fn outfmt_of(arg: OutFmtArg) -> OutFmt {
+    match arg {
+        OutFmtArg::Raw => OutFmt::Raw,
+        OutFmtArg::Hex => OutFmt::Hex,
+        OutFmtArg::C   => OutFmt::C,
+        OutFmtArg::Py  => OutFmt::Py,
+    }
+}
+
+pub fn infmt_of(arg: InFmtArg) -> InFmt {
+    match arg {
+        InFmtArg::Raw => InFmt::Raw,
+        InFmtArg::Hex => InFmt::Hex,
+    }
+}
+
+pub fn parse_width(s: &str) -> Result<Width, String> {
+    s.parse().map_err(|e: crate::PakxError| e.to_string())
+}
+
+pub fn parse_badchars(s: &str) -> Result<BadChars, String> {
+    parse_hex_str(s).map(|b| BadChars::new(&b)).map_err(|e| e.to_string())
+}
+
+pub fn parse_expr(s: &str) -> Result<i128, String> {
+    crate::util::parse_expr(s).map_err(|e| e.to_string())
+}
+
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    let n = parse_expr(s)?;
+    usize::try_from(n).map_err(|_| format!("size must not be negative: {s}"))
+}
+
+pub fn parse_u64(s: &str) -> Result<u64, String> {
+    let n = parse_int(s).map_err(|e| e.to_string())?;
+    u64::try_from(n).map_err(|_| format!("value out of range for 64 bits: {s}"))
+}
+
+pub fn parse_byte(s: &str) -> Result<u8, String> {
+    let n = parse_int(s).map_err(|e| e.to_string())?;
+    u8::try_from(n).map_err(|_| format!("byte value out of range: {s}"))
+}
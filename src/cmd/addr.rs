@@ -0,0 +1,199 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use crate::api::Formatter;
+use crate::cli::{BadcharArgs, InFmtArg, OutFmtArg};
+use crate::error::PakxError;
+use crate::util::{parse_int, read_input, Endian, InFmt};
+
+#[derive(Subcommand)]
+pub enum AddrCmd {
+    Pack(AddrPackArgs),
+    Unpack(AddrUnpackArgs),
+}
+
+#[derive(Args)]
+pub struct AddrPackArgs {
+    /// Reverse every value instead of using network byte order.
+    #[arg(long)]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// IPv4, IPv6, MAC addresses or ports (bare integers).
+    #[arg(required = true)]
+    pub values: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct AddrUnpackArgs {
+    #[arg(long)]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    /// Kinds to decode, repeating the list until input runs out.
+    #[arg(value_enum, value_delimiter = ',', required = true)]
+    pub kinds: Vec<AddrKind>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum AddrKind {
+    Ipv4,
+    Ipv6,
+    Mac,
+    Port,
+}
+
+impl AddrKind {
+    pub fn size(self) -> usize {
+        match self {
+            AddrKind::Ipv4 => 4,
+            AddrKind::Ipv6 => 16,
+            AddrKind::Mac => 6,
+            AddrKind::Port => 2,
+        }
+    }
+}
+
+/// An address or port, held in network byte order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Addr {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Mac([u8; 6]),
+    Port(u16),
+}
+
+fn parse_mac(s: &str) -> Option<[u8; 6]> {
+    let sep = if s.contains('-') { '-' } else { ':' };
+    let parts: Vec<&str> = s.split(sep).collect();
+    if parts.len() != 6 || parts.iter().any(|p| p.len() != 2 || !p.bytes().all(|b| b.is_ascii_hexdigit())) {
+        return None;
+    }
+    let mut mac = [0; 6];
+    for (b, p) in mac.iter_mut().zip(parts) {
+        *b = u8::from_str_radix(p, 16).ok()?;
+    }
+    Some(mac)
+}
+
+/// Dotted quads, IPv6 (with `::`), MACs as `aa:bb:..` or `aa-bb-..`, and
+/// bare integers as ports.
+pub fn parse_addr(s: &str) -> Result<Addr> {
+    if let Ok(ip) = s.parse::<Ipv4Addr>() {
+        return Ok(Addr::Ipv4(ip));
+    }
+    if let Some(mac) = parse_mac(s) {
+        return Ok(Addr::Mac(mac));
+    }
+    if let Ok(ip) = s.parse::<Ipv6Addr>() {
+        return Ok(Addr::Ipv6(ip));
+    }
+    if s.contains(['.', ':', '-']) && !s.starts_with('-') {
+        return Err(anyhow!("not an IPv4, IPv6 or MAC address: {s}"));
+    }
+    let n = parse_int(s)?;
+    let port = u16::try_from(n).map_err(|_| anyhow!("port out of range: {s}"))?;
+    Ok(Addr::Port(port))
+}
+
+impl Addr {
+    pub fn kind(&self) -> AddrKind {
+        match self {
+            Addr::Ipv4(_) => AddrKind::Ipv4,
+            Addr::Ipv6(_) => AddrKind::Ipv6,
+            Addr::Mac(_) => AddrKind::Mac,
+            Addr::Port(_) => AddrKind::Port,
+        }
+    }
+
+    /// Network byte order for `Endian::Big`, fully reversed for `Little`.
+    pub fn to_bytes(&self, endian: Endian) -> Vec<u8> {
+        let mut b = match self {
+            Addr::Ipv4(ip) => ip.octets().to_vec(),
+            Addr::Ipv6(ip) => ip.octets().to_vec(),
+            Addr::Mac(m) => m.to_vec(),
+            Addr::Port(p) => p.to_be_bytes().to_vec(),
+        };
+        if endian == Endian::Little {
+            b.reverse();
+        }
+        b
+    }
+
+    /// Inverse of `to_bytes`; fails unless `bytes` is exactly `kind.size()` long.
+    pub fn from_bytes(kind: AddrKind, bytes: &[u8], endian: Endian) -> Result<Addr> {
+        if bytes.len() != kind.size() {
+            return Err(anyhow!("{kind:?} address needs {} bytes, got {}", kind.size(), bytes.len()));
+        }
+        let mut b = bytes.to_vec();
+        if endian == Endian::Little {
+            b.reverse();
+        }
+        Ok(match kind {
+            AddrKind::Ipv4 => Addr::Ipv4(Ipv4Addr::from([b[0], b[1], b[2], b[3]])),
+            AddrKind::Ipv6 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(&b);
+                Addr::Ipv6(Ipv6Addr::from(octets))
+            }
+            AddrKind::Mac => Addr::Mac([b[0], b[1], b[2], b[3], b[4], b[5]]),
+            AddrKind::Port => Addr::Port(u16::from_be_bytes([b[0], b[1]])),
+        })
+    }
+}
+
+impl std::fmt::Display for Addr {
+    /// Canonical text: RFC 5952 compression for IPv6, lowercase colon MACs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Addr::Ipv4(ip) => write!(f, "{ip}"),
+            Addr::Ipv6(ip) => write!(f, "{ip}"),
+            Addr::Mac(m) => {
+                let parts: Vec<String> = m.iter().map(|b| format!("{b:02x}")).collect();
+                write!(f, "{}", parts.join(":"))
+            }
+            Addr::Port(p) => write!(f, "{p}"),
+        }
+    }
+}
+
+pub fn run_addr_pack<W: Write>(values: &[String], endian: Endian, fmt: &Formatter, out: &mut W) -> Result<()> {
+    let mut bytes = Vec::new();
+    for (i, v) in values.iter().enumerate() {
+        let a = parse_addr(v).map_err(|e| anyhow!("value {i} ({v}): {e}"))?;
+        bytes.extend(a.to_bytes(endian));
+    }
+    fmt.write(out, &bytes)
+}
+
+/// Decode the input as `kinds`, cycling through them until it runs out, and
+/// print one address per line.
+pub fn run_addr_unpack<R: Read, W: Write>(
+    kinds: &[AddrKind],
+    endian: Endian,
+    infmt: InFmt,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    let mut off = 0;
+    for kind in kinds.iter().cycle() {
+        if off == data.len() {
+            break;
+        }
+        let n = kind.size();
+        if off + n > data.len() {
+            return Err(PakxError::Truncated { needed: n, got: data.len() - off, offset: off }.into());
+        }
+        writeln!(out, "{}", Addr::from_bytes(*kind, &data[off..off + n], endian)?)?;
+        off += n;
+    }
+    Ok(())
+}
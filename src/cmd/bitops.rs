@@ -0,0 +1,109 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::cli::{parse_width, ByteIoArgs};
+use crate::error::PakxError;
+use crate::util::{parse_int, read_input, try_pack, try_unpack, Endian, InFmt, Width};
+
+// Like bswap, the bits subcommands print hex unless --out says otherwise.
+#[derive(Args)]
+#[command(mut_arg("out", |a| a.default_value("hex")))]
+pub struct BitOpArgs {
+    /// Word size; the input must be a whole number of words.
+    #[arg(long, value_parser = parse_width, default_value = "8")]
+    pub width: Width,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    /// Transform this value instead of reading stdin.
+    #[arg(allow_negative_numbers = true)]
+    pub value: Option<String>,
+    #[command(flatten)]
+    pub io: ByteIoArgs,
+}
+
+#[derive(Args)]
+pub struct RotateArgs {
+    /// Number of bits to rotate by.
+    #[arg(long)]
+    pub by: u32,
+    #[command(flatten)]
+    pub op: BitOpArgs,
+}
+
+/// Transform applied to each word of a stream.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BitOp {
+    /// Reverse the bit order of the whole word.
+    Reverse,
+    /// Swap the high and low nibble of every byte.
+    SwapNibbles,
+    Rol(u32),
+    Ror(u32),
+}
+
+/// Apply `op` to the low `width` bits of `v`.
+pub fn apply_word(op: BitOp, v: u128, width: Width) -> u128 {
+    let w = width.bits();
+    let mask = if w == 128 { u128::MAX } else { (1 << w) - 1 };
+    let v = v & mask;
+    let rotl = |n: u32| {
+        let n = n % w;
+        if n == 0 { v } else { ((v << n) | (v >> (w - n))) & mask }
+    };
+    match op {
+        BitOp::Reverse => v.reverse_bits() >> (128 - w),
+        BitOp::SwapNibbles => {
+            const LOW: u128 = u128::MAX / 0x11; // 0x0f0f...0f
+            ((v & LOW) << 4) | ((v >> 4) & LOW)
+        }
+        BitOp::Rol(n) => rotl(n),
+        BitOp::Ror(n) => rotl(w - n % w),
+    }
+}
+
+/// Split `data` into `width` words read with `endian`, transform each and
+/// write it back in the same byte order.
+pub fn transform(op: BitOp, data: &[u8], width: Width, endian: Endian) -> Result<Vec<u8>> {
+    let n = width.bytes();
+    if data.len() % n != 0 {
+        let offset = data.len() / n * n;
+        return Err(PakxError::Truncated { needed: n, got: data.len() - offset, offset }.into());
+    }
+    let mut out = Vec::with_capacity(data.len());
+    for word in data.chunks(n) {
+        let v = try_unpack(word, width, endian, false)? as u128;
+        out.extend(try_pack(apply_word(op, v, width) as i128, width, endian, false, false)?);
+    }
+    Ok(out)
+}
+
+pub fn run_bitop<R: Read, W: Write>(
+    op: BitOp,
+    width: Width,
+    endian: Endian,
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    fmt.write(out, &transform(op, &data, width, endian)?)
+}
+
+/// Transform a single value packed at `width`, like `bswap` does. The value
+/// must fit the width: unsigned, or signed when negative.
+pub fn run_bitop_value<W: Write>(
+    op: BitOp,
+    width: Width,
+    endian: Endian,
+    value: &str,
+    fmt: &Formatter,
+    out: &mut W,
+) -> Result<()> {
+    let n = parse_int(value)?;
+    let data = try_pack(n, width, endian, n < 0, true)?;
+    fmt.write(out, &transform(op, &data, width, endian)?)
+}
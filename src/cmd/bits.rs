@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+use std::io::{Read, Write};
+use crate::api::{Formatter, Packer, Unpacker};
+use crate::cli::{parse_width, BadcharArgs, InFmtArg, OutFmtArg};
+use crate::cmd::bitops::{BitOpArgs, RotateArgs};
+use crate::error::PakxError;
+use crate::util::Width;
+
+#[derive(Subcommand)]
+pub enum BitsCmd {
+    Pack(BitsPackArgs),
+    Unpack(BitsUnpackArgs),
+    /// Reverse the bit order of each word.
+    Rev(BitOpArgs),
+    /// Swap the nibbles of each byte.
+    Nibswap(BitOpArgs),
+    /// Rotate each word left.
+    Rol(RotateArgs),
+    /// Rotate each word right.
+    Ror(RotateArgs),
+}
+
+#[derive(Args)]
+pub struct BitsPackArgs {
+    #[arg(long, value_parser = parse_width)]
+    pub width: Width,
+    #[arg(long)]
+    pub msb0: bool,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long)]
+    pub signed: bool,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Hex)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    #[arg(allow_negative_numbers = true)]
+    pub fields: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct BitsUnpackArgs {
+    #[arg(long, value_parser = parse_width)]
+    pub width: Width,
+    #[arg(long, value_delimiter = ',', required = true)]
+    pub fields: Vec<u32>,
+    #[arg(long)]
+    pub msb0: bool,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long)]
+    pub signed: bool,
+}
+
+/// Bit numbering used to place fields inside a word.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BitOrder {
+    Lsb0, // first field starts at bit 0
+    Msb0, // first field starts at the most significant bit
+}
+
+impl BitOrder {
+    pub fn from_msb0(msb0: bool) -> Self {
+        if msb0 { BitOrder::Msb0 } else { BitOrder::Lsb0 }
+    }
+}
+
+/// Parse a `LEN:VALUE` field spec, e.g. `3:0b101` or `8:0xff`.
+pub fn parse_field(s: &str) -> Result<(u32, i128)> {
+    let (len, val) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("field must be LEN:VALUE, got: {s}"))?;
+    let len: u32 = len.trim().parse().map_err(|_| anyhow!("bad field length in: {s}"))?;
+    if len == 0 {
+        return Err(anyhow!("field length must be at least 1: {s}"));
+    }
+    Ok((len, crate::util::parse_int(val)?))
+}
+
+fn shift_of(offset: u32, len: u32, width_bits: u32, order: BitOrder) -> u32 {
+    match order {
+        BitOrder::Lsb0 => offset,
+        BitOrder::Msb0 => width_bits - offset - len,
+    }
+}
+
+fn field_mask(len: u32) -> u128 {
+    if len == 128 { u128::MAX } else { (1u128 << len) - 1 }
+}
+
+/// Pack `(len, value)` fields into a single word of `width_bits`.
+pub fn pack_fields(fields: &[(u32, i128)], width_bits: u32, order: BitOrder, signed: bool) -> Result<u128> {
+    let total: u64 = fields.iter().map(|(len, _)| u64::from(*len)).sum();
+    if total > u64::from(width_bits) {
+        return Err(anyhow!("fields use {total} bits but width is {width_bits}"));
+    }
+
+    let mut word = 0u128;
+    let mut offset = 0u32;
+    for (i, &(len, n)) in fields.iter().enumerate() {
+        let fits = if signed {
+            len >= 128 || {
+                let half = 1i128 << (len - 1);
+                n >= -half && n < half
+            }
+        } else {
+            n >= 0 && (len >= 127 || n < (1i128 << len))
+        };
+        if !fits {
+            return Err(PakxError::FieldOverflow { value: n, bits: len, signed, field: i }.into());
+        }
+
+        let v = (n as u128) & field_mask(len);
+        word |= v << shift_of(offset, len, width_bits, order);
+        offset += len;
+    }
+    Ok(word)
+}
+
+/// Split a word into fields of the given bit lengths.
+pub fn unpack_fields(word: u128, lens: &[u32], width_bits: u32, order: BitOrder, signed: bool) -> Result<Vec<i128>> {
+    let total: u64 = lens.iter().map(|&len| u64::from(len)).sum();
+    if total > u64::from(width_bits) {
+        return Err(anyhow!("fields use {total} bits but width is {width_bits}"));
+    }
+
+    let mut out = Vec::with_capacity(lens.len());
+    let mut offset = 0u32;
+    for &len in lens {
+        if len == 0 {
+            return Err(anyhow!("field length must be at least 1"));
+        }
+        let v = (word >> shift_of(offset, len, width_bits, order)) & field_mask(len);
+        let n = if signed && len < 128 && (v >> (len - 1)) & 1 == 1 {
+            (v | !field_mask(len)) as i128
+        } else {
+            v as i128
+        };
+        out.push(n);
+        offset += len;
+    }
+    Ok(out)
+}
+
+/// `packer` encodes the finished word; `signed` applies to the field values.
+pub fn run_bits_pack<W: Write>(
+    packer: &Packer,
+    fields: &[String],
+    order: BitOrder,
+    signed: bool,
+    fmt: &Formatter,
+    out: &mut W,
+) -> Result<()> {
+    let parsed = fields.iter().map(|f| parse_field(f)).collect::<Result<Vec<_>>>()?;
+    let word = pack_fields(&parsed, packer.width().bits(), order, signed)?;
+    let b = packer.pack_ints(&[word as i128])?;
+    fmt.write(out, &b)
+}
+
+/// `words` decodes the input into unsigned words; `signed` applies to the fields.
+pub fn run_bits_unpack<R: Read, W: Write>(
+    words: &Unpacker,
+    lens: &[u32],
+    order: BitOrder,
+    signed: bool,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    for word in words.read(input)? {
+        let vals = unpack_fields(word as u128, lens, words.width().bits(), order, signed)?;
+        let line: Vec<String> = vals.iter().map(|v| v.to_string()).collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
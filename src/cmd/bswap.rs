@@ -1,15 +1,16 @@
 use anyhow::Result;
-use crate::util::{pack_scalar, write_bytes, Endian, OutFmt};
+use std::io::Write;
+use crate::api::Formatter;
+use crate::util::{try_pack, Endian, Width};
 
-pub fn run_bswap(
-    width_bits: u32,
+pub fn run_bswap<W: Write>(
+    width: Width,
     value: &str,
-    outfmt: OutFmt,
-    sep: &str,
-    uppercase: bool,
+    fmt: &Formatter,
+    out: &mut W,
 ) -> Result<()> {
     let n = crate::util::parse_int(value)?;
-    let mut b = pack_scalar(n, width_bits, Endian::Big, /*signed*/ false, /*strict*/ false)?;
+    let mut b = try_pack(n, width, Endian::Big, /*signed*/ false, /*strict*/ false)?;
     b.reverse();
-    write_bytes(outfmt, &b, sep, uppercase)
+    fmt.write(out, &b)
 }
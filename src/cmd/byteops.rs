@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use crate::api::Formatter;
+use crate::cli::{parse_byte, ByteIoArgs};
+use crate::util::{parse_hex_str, read_input, InFmt};
+
+#[derive(Args)]
+pub struct KeyArgs {
+    /// Single key byte, e.g. 0x41.
+    #[arg(long, value_parser = parse_byte, conflicts_with_all = ["key_hex", "key_file"])]
+    pub key: Option<u8>,
+    /// Multi-byte key as hex, e.g. "de ad be ef".
+    #[arg(long, conflicts_with = "key_file")]
+    pub key_hex: Option<String>,
+    /// Read the key bytes from a file.
+    #[arg(long)]
+    pub key_file: Option<PathBuf>,
+    /// Add N to every key byte each time the key wraps around.
+    #[arg(long, value_parser = parse_byte, default_value = "0")]
+    pub roll: u8,
+}
+
+impl KeyArgs {
+    pub fn key(&self) -> Result<Key> {
+        let bytes = match (self.key, &self.key_hex, &self.key_file) {
+            (Some(k), _, _) => vec![k],
+            (_, Some(h), _) => parse_hex_str(h)?,
+            (_, _, Some(p)) => std::fs::read(p)?,
+            _ => bail!("one of --key, --key-hex or --key-file is required"),
+        };
+        Ok(Key::new(bytes).roll(self.roll))
+    }
+}
+
+#[derive(Args)]
+pub struct ByteOpArgs {
+    #[command(flatten)]
+    pub key: KeyArgs,
+    #[command(flatten)]
+    pub io: ByteIoArgs,
+}
+
+#[derive(Args)]
+pub struct XorArgs {
+    #[command(flatten)]
+    pub op: ByteOpArgs,
+    /// Try every single-byte key and rank them by printable output.
+    #[arg(long, conflicts_with_all = ["key", "key_hex", "key_file"])]
+    pub brute: bool,
+    /// Number of candidates shown by --brute.
+    #[arg(long, default_value_t = 10, requires = "brute")]
+    pub top: usize,
+}
+
+/// Byte-wise operation applied between the input and a repeating key.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ByteOp {
+    Xor,
+    And,
+    Or,
+    Not,
+    Add, // mod 256
+    Sub, // mod 256
+}
+
+impl ByteOp {
+    fn apply(self, b: u8, k: u8) -> u8 {
+        match self {
+            ByteOp::Xor => b ^ k,
+            ByteOp::And => b & k,
+            ByteOp::Or => b | k,
+            ByteOp::Not => !b,
+            ByteOp::Add => b.wrapping_add(k),
+            ByteOp::Sub => b.wrapping_sub(k),
+        }
+    }
+}
+
+/// A key repeated over the input. With a non-zero `roll`, every key byte
+/// advances by `roll` (mod 256) each time the key wraps around.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Key {
+    pub bytes: Vec<u8>,
+    pub roll: u8,
+}
+
+impl Key {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, roll: 0 }
+    }
+
+    pub fn roll(mut self, step: u8) -> Self {
+        self.roll = step;
+        self
+    }
+
+    fn at(&self, i: usize) -> u8 {
+        let n = self.bytes.len();
+        let turns = (i / n) as u8; // wraps mod 256 like the key bytes do
+        self.bytes[i % n].wrapping_add(self.roll.wrapping_mul(turns))
+    }
+}
+
+pub fn apply_op(op: ByteOp, data: &[u8], key: &Key) -> Result<Vec<u8>> {
+    if op == ByteOp::Not {
+        return Ok(data.iter().map(|&b| !b).collect());
+    }
+    if key.bytes.is_empty() {
+        bail!("key must not be empty");
+    }
+    Ok(data.iter().enumerate().map(|(i, &b)| op.apply(b, key.at(i))).collect())
+}
+
+pub fn run_byteop<R: Read, W: Write>(
+    op: ByteOp,
+    key: &Key,
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    fmt.write(out, &apply_op(op, &data, key)?)
+}
+
+fn printable(b: u8) -> bool {
+    matches!(b, 0x20..=0x7e | b'\t' | b'\n' | b'\r')
+}
+
+/// Share of printable bytes in `data`.
+pub fn score(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    data.iter().filter(|&&b| printable(b)).count() as f64 / data.len() as f64
+}
+
+/// Every single-byte XOR key with its score, best first; ties go to the key
+/// whose output has more letters and spaces.
+pub fn brute_xor(data: &[u8]) -> Vec<(u8, f64)> {
+    let mut ranked: Vec<(u8, f64, usize)> = (0..=255u8)
+        .map(|k| {
+            let out: Vec<u8> = data.iter().map(|&b| b ^ k).collect();
+            let texty = out.iter().filter(|&&b| b.is_ascii_alphabetic() || b == b' ').count();
+            (k, score(&out), texty)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+    ranked.into_iter().map(|(k, s, _)| (k, s)).collect()
+}
+
+/// One line per candidate: key, percentage of printable output, preview.
+pub fn run_xor_brute<R: Read, W: Write>(infmt: InFmt, top: usize, input: R, out: &mut W) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    for (k, s) in brute_xor(&data).into_iter().take(top) {
+        let preview: String = data
+            .iter()
+            .take(48)
+            .map(|&b| b ^ k)
+            .map(|b| if (0x20..=0x7e).contains(&b) { b as char } else { '.' })
+            .collect();
+        writeln!(out, "0x{k:02x}  {:5.1}%  {preview}", s * 100.0)?;
+    }
+    Ok(())
+}
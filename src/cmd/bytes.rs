@@ -1,12 +1,14 @@
 use anyhow::Result;
-use crate::util::{read_stdin_hex, read_stdin_raw, write_bytes, OutFmt};
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::util::{read_input, InFmt};
 
-pub fn run_bytes(
-    input_hex: bool,  // true = hex, false = raw
-    outfmt: OutFmt,
-    sep: &str,
-    uppercase: bool,
+pub fn run_bytes<R: Read, W: Write>(
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
 ) -> Result<()> {
-    let data = if input_hex { read_stdin_hex()? } else { read_stdin_raw()? };
-    write_bytes(outfmt, &data, sep, uppercase)
-}
\ No newline at end of file
+    let data = read_input(input, infmt)?;
+    fmt.write(out, &data)
+}
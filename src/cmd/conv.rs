@@ -0,0 +1,88 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::Write;
+use crate::util::{pack_scalar, parse_int, parse_int_type, unpack_scalar, Endian, Width};
+
+#[derive(Args)]
+pub struct ConvArgs {
+    /// Source type, e.g. i16.
+    #[arg(long, value_parser = parse_type)]
+    pub from: IntType,
+    /// Target type, e.g. u64.
+    #[arg(long, value_parser = parse_type)]
+    pub to: IntType,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(allow_negative_numbers = true)]
+    pub value: String,
+}
+
+fn parse_type(s: &str) -> Result<IntType, String> {
+    parse_int_type(s).ok_or_else(|| format!("expected a type such as u8, i16 or u64, got: {s}"))
+}
+
+/// An integer type such as `i16`: width and signedness.
+pub type IntType = (Width, bool);
+
+fn type_name((w, signed): IntType) -> String {
+    format!("{}{}", if signed { 'i' } else { 'u' }, w.bits())
+}
+
+/// What a C-style cast from one integer type to another does to the bits.
+pub fn describe(from: IntType, to: IntType) -> &'static str {
+    match from.0.bits().cmp(&to.0.bits()) {
+        std::cmp::Ordering::Less if from.1 => "sign-extend",
+        std::cmp::Ordering::Less => "zero-extend",
+        std::cmp::Ordering::Greater => "truncate",
+        std::cmp::Ordering::Equal if from.1 != to.1 => "reinterpret",
+        std::cmp::Ordering::Equal => "identity",
+    }
+}
+
+/// The result of casting a value between integer types.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conv {
+    /// The input bit pattern read as the source type.
+    pub input: i128,
+    pub output: i128,
+    /// `output` packed at the target width.
+    pub bytes: Vec<u8>,
+}
+
+/// Mask `value` to the source width, read it as the source type, then cast
+/// it to the target type the way C does.
+pub fn convert(value: i128, from: IntType, to: IntType, endian: Endian) -> Result<Conv> {
+    let src = pack_scalar(value, from.0.bits(), endian, from.1, false)?;
+    let input = unpack_scalar(&src, from.0.bits(), endian, from.1)?;
+    let bytes = pack_scalar(input, to.0.bits(), endian, to.1, false)?;
+    let output = unpack_scalar(&bytes, to.0.bits(), endian, to.1)?;
+    Ok(Conv { input, output, bytes })
+}
+
+/// Decimal text for `v` as `ty`; u128 values above i128::MAX are stored wrapped.
+fn decimal(v: i128, (w, signed): IntType) -> String {
+    if !signed && w.bits() == 128 { (v as u128).to_string() } else { v.to_string() }
+}
+
+fn pattern(v: i128, w: Width) -> String {
+    let bits = v as u128 & if w.bits() == 128 { u128::MAX } else { (1 << w.bits()) - 1 };
+    format!("0x{bits:0digits$x}", digits = w.bytes() * 2)
+}
+
+pub fn run_conv<W: Write>(value: &str, from: IntType, to: IntType, endian: Endian, out: &mut W) -> Result<()> {
+    let c = convert(parse_int(value)?, from, to, endian)?;
+    let bytes = c.bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(" ");
+    let (old, new) = (decimal(c.input, from), decimal(c.output, to));
+    writeln!(out, "from  {:<4}  {old}  ({})", type_name(from), pattern(c.input, from.0))?;
+    writeln!(out, "to    {:<4}  {new}  ({})", type_name(to), pattern(c.output, to.0))?;
+    let changed = if old == new { "" } else { ", value changed" };
+    writeln!(out, "op    {}{changed}", describe(from, to))?;
+    let e = match endian {
+        Endian::Little => "le",
+        Endian::Big => "be",
+    };
+    writeln!(out, "{e}    {bytes}")?;
+    Ok(())
+}
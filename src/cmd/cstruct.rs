@@ -0,0 +1,52 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use crate::cli::InFmtArg;
+use crate::cstruct::{Abi, Header};
+use crate::util::{read_input, Endian, InFmt};
+
+#[derive(Args)]
+pub struct CstructArgs {
+    /// C header containing the struct declaration.
+    pub header: PathBuf,
+    #[arg(long = "struct")]
+    pub name: Option<String>,
+    #[arg(long, value_enum, default_value_t = Abi::Lp64)]
+    pub abi: Abi,
+    /// Print offsets, sizes and padding instead of decoding data.
+    #[arg(long)]
+    pub show: bool,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    /// Binary input file; reads stdin when omitted.
+    #[arg(long)]
+    pub data: Option<PathBuf>,
+}
+
+/// Print the layout of `name` (or the last struct in the header).
+pub fn run_cstruct_show<W: Write>(header: &Header, name: Option<&str>, out: &mut W) -> Result<()> {
+    let layout = header.pick(name)?;
+    out.write_all(header.render(layout).as_bytes())?;
+    Ok(())
+}
+
+/// Decode one struct from `input` into JSON with named fields.
+pub fn run_cstruct_decode<R: Read, W: Write>(
+    header: &Header,
+    name: Option<&str>,
+    endian: Endian,
+    infmt: InFmt,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let layout = header.pick(name)?;
+    let schema = header.to_schema(layout, endian)?;
+    let data = read_input(input, infmt)?;
+    out.write_all(schema.decode(&data)?.to_pretty().as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use crate::cli::InFmtArg;
+use crate::schema::Schema;
+use crate::util::{read_input, InFmt};
+
+#[derive(Args)]
+pub struct DecodeArgs {
+    #[arg(long)]
+    pub schema: PathBuf,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    /// Input file; reads stdin when omitted.
+    pub file: Option<PathBuf>,
+}
+
+pub fn run_decode<R: Read, W: Write>(
+    schema: &Schema,
+    infmt: InFmt,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    let json = schema.decode(&data)?;
+    out.write_all(json.to_pretty().as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+use crate::cli::{endian_from, parse_width};
+use crate::util::{unpack_scalar, Endian, Width};
+
+#[derive(Args)]
+pub struct DiffArgs {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    /// Also show changed words of this width as integers.
+    #[arg(long, value_parser = parse_width)]
+    pub width: Option<Width>,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long)]
+    pub signed: bool,
+    /// Print nothing; exit 0 if the files are identical, 1 if they differ.
+    #[arg(long, short)]
+    pub silent: bool,
+}
+
+impl DiffArgs {
+    pub fn opts(&self) -> DiffOpts {
+        DiffOpts { width: self.width, endian: endian_from(self.be, self.le), signed: self.signed, silent: self.silent }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct DiffOpts {
+    /// Also decode changed words of this width.
+    pub width: Option<Width>,
+    pub endian: Endian,
+    pub signed: bool,
+    /// Print nothing; only the exit status matters.
+    pub silent: bool,
+}
+
+/// Runs of differing bytes over the common length of `a` and `b`.
+pub fn diff_ranges(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for i in (0..a.len().min(b.len())).filter(|&i| a[i] != b[i]) {
+        match ranges.last_mut() {
+            Some(r) if r.end == i => r.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+/// Offsets of the whole `n`-byte words that overlap any of `ranges`, each
+/// once, in order; words running past `common` are left out.
+pub fn changed_words(ranges: &[Range<usize>], n: usize, common: usize) -> Vec<usize> {
+    let mut words: Vec<usize> = ranges
+        .iter()
+        .flat_map(|r| (r.start / n * n..r.end).step_by(n))
+        .filter(|off| off + n <= common)
+        .collect();
+    words.dedup();
+    words
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|x| format!("{x:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+/// Print each differing range as side-by-side hex, plus the changed words
+/// when `opts.width` is set. Returns whether the inputs differ.
+pub fn run_diff<W: Write>(names: (&str, &str), a: &[u8], b: &[u8], opts: &DiffOpts, out: &mut W) -> Result<bool> {
+    let ranges = diff_ranges(a, b);
+    let differ = !ranges.is_empty() || a.len() != b.len();
+    if opts.silent {
+        return Ok(differ);
+    }
+    let common = a.len().min(b.len());
+    for r in &ranges {
+        writeln!(out, "0x{:08x}  {} byte(s) differ", r.start, r.len())?;
+        for off in r.clone().step_by(16) {
+            let end = (off + 16).min(r.end);
+            writeln!(out, "  0x{off:08x}  {:<47}  |  {}", hex(&a[off..end]), hex(&b[off..end]))?;
+        }
+    }
+    if let Some(w) = opts.width {
+        let words = changed_words(&ranges, w.bytes(), common);
+        if !words.is_empty() {
+            writeln!(out, "{}-bit words that differ:", w.bits())?;
+        }
+        for off in words {
+            let n = w.bytes();
+            let old = unpack_scalar(&a[off..off + n], w.bits(), opts.endian, opts.signed)?;
+            let new = unpack_scalar(&b[off..off + n], w.bits(), opts.endian, opts.signed)?;
+            writeln!(out, "  offset 0x{off:x}: {old} -> {new}")?;
+        }
+    }
+    if a.len() != b.len() {
+        writeln!(out, "size differs: {} is {} bytes, {} is {} bytes", names.0, a.len(), names.1, b.len())?;
+    }
+    out.flush()?;
+    Ok(differ)
+}
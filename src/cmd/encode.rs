@@ -0,0 +1,36 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use crate::api::Formatter;
+use crate::cli::{BadcharArgs, OutFmtArg};
+use crate::json::Json;
+use crate::schema::Schema;
+
+#[derive(Args)]
+pub struct EncodeArgs {
+    #[arg(long)]
+    pub schema: PathBuf,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// JSON input file; reads stdin when omitted.
+    pub file: Option<PathBuf>,
+}
+
+pub fn run_encode<R: Read, W: Write>(
+    schema: &Schema,
+    fmt: &Formatter,
+    mut input: R,
+    out: &mut W,
+) -> Result<()> {
+    let mut src = String::new();
+    input.read_to_string(&mut src)?;
+    let buf = schema.encode(&Json::parse(&src)?)?;
+    fmt.write(out, &buf)
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use crate::cli::{endian_from, PackedValueArgs};
+use crate::util::{read_raw, Endian};
+
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("value").required(true).args(["p8", "p16", "p32", "p64", "p128"]))]
+pub struct FindArgs {
+    /// File to search; reads stdin when omitted.
+    pub file: Option<PathBuf>,
+    #[command(flatten)]
+    pub value: PackedValueArgs,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    /// Search for both the little- and big-endian encodings.
+    #[arg(long, conflicts_with_all = ["be", "le"])]
+    pub any_endian: bool,
+    /// Only report offsets that are a multiple of the value's size.
+    #[arg(long)]
+    pub aligned: bool,
+    /// Stop after N hits.
+    #[arg(long)]
+    pub max: Option<usize>,
+}
+
+impl FindArgs {
+    /// The packed value in each requested byte order, labelled when there
+    /// is more than one.
+    pub fn needles(&self) -> Result<Vec<(Vec<u8>, &'static str)>> {
+        let endians: &[(Endian, &str)] = if self.any_endian {
+            &[(Endian::Little, "le"), (Endian::Big, "be")]
+        } else {
+            &[(endian_from(self.be, self.le), "")]
+        };
+        let mut needles = Vec::new();
+        for &(e, label) in endians {
+            // A masked needle would report hits for a value that was never asked for.
+            let needle = self.value.packed(e, true)?.expect("clap requires a value");
+            needles.push((needle, label));
+        }
+        Ok(needles)
+    }
+}
+
+/// Offsets of `needle` in `hay`, only at multiples of its length when
+/// `aligned`, stopping after `max` hits.
+pub fn find_all(hay: &[u8], needle: &[u8], aligned: bool, max: Option<usize>) -> Vec<usize> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let step = if aligned { needle.len() } else { 1 };
+    hay.windows(needle.len())
+        .enumerate()
+        .step_by(step)
+        .filter(|(_, w)| *w == needle)
+        .map(|(i, _)| i)
+        .take(max.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Search for every labelled needle and print one offset per line, sorted.
+/// Labels are printed after the offset when there is more than one needle.
+pub fn run_find<R: Read, W: Write>(
+    needles: &[(Vec<u8>, &str)],
+    aligned: bool,
+    max: Option<usize>,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let hay = read_raw(input)?;
+    let mut hits: Vec<(usize, &str)> = Vec::new();
+    for (i, (needle, label)) in needles.iter().enumerate() {
+        // A value that reads the same in both byte orders is only reported once.
+        if needles[..i].iter().any(|(n, _)| n == needle) {
+            continue;
+        }
+        hits.extend(find_all(&hay, needle, aligned, max).into_iter().map(|off| (off, *label)));
+    }
+    hits.sort();
+    hits.truncate(max.unwrap_or(usize::MAX));
+    for (off, label) in hits {
+        if needles.len() > 1 {
+            writeln!(out, "0x{off:08x}  {label}")?;
+        } else {
+            writeln!(out, "0x{off:08x}")?;
+        }
+    }
+    Ok(())
+}
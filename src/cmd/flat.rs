@@ -0,0 +1,197 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use std::io::Write;
+use std::ops::Range;
+use crate::api::{parse_item, Formatter, Item};
+use crate::cli::{endian_from, parse_byte, parse_width, BadcharArgs, OutFmtArg};
+use crate::util::{parse_hex_str, parse_int, try_pack, unescape, write_span, Endian, Width, MAX_OUTPUT};
+
+#[derive(Args)]
+pub struct FlatArgs {
+    /// Width for integers given without a TYPE: prefix.
+    #[arg(long, value_parser = parse_width, default_value = "64")]
+    pub width: Width,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long)]
+    pub strict: bool,
+    /// Byte used by fill:N and @OFFSET padding.
+    #[arg(long, value_parser = parse_byte, default_value = "0")]
+    pub filler: u8,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// Do not print the offset map on stderr.
+    #[arg(long, short)]
+    pub quiet: bool,
+    /// u64:VALUE, str:TEXT, hex:BYTES, fill:N, rep:N:ITEM or @OFFSET.
+    #[arg(allow_negative_numbers = true)]
+    pub items: Vec<String>,
+}
+
+impl FlatArgs {
+    pub fn opts(&self) -> FlatOpts {
+        FlatOpts { width: self.width, endian: endian_from(self.be, self.le), strict: self.strict, filler: self.filler }
+    }
+}
+
+/// One argument to `flat`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FlatItem {
+    /// `u64:0xdeadbeef`, or a bare integer packed at the default width.
+    Int(Item),
+    /// `str:TEXT` (with `\xHH` escapes) or `hex:DEADBEEF`.
+    Bytes(Vec<u8>),
+    /// `fill:N` filler bytes.
+    Fill(usize),
+    /// `rep:N:ITEM` repeats any other item.
+    Repeat(usize, Box<FlatItem>),
+    /// `@OFFSET` pads with filler up to an absolute offset.
+    At(usize),
+}
+
+fn parse_len(s: &str) -> Result<usize> {
+    let n = parse_int(s)?;
+    let n = usize::try_from(n).map_err(|_| anyhow!("length must not be negative: {s}"))?;
+    if n > MAX_OUTPUT {
+        bail!("{s} exceeds the {MAX_OUTPUT}-byte limit");
+    }
+    Ok(n)
+}
+
+/// `len + extra`, if the result stays within `MAX_OUTPUT`.
+fn grow(len: usize, extra: usize) -> Result<usize> {
+    len.checked_add(extra)
+        .filter(|&n| n <= MAX_OUTPUT)
+        .ok_or_else(|| anyhow!("payload would exceed the {MAX_OUTPUT}-byte limit"))
+}
+
+pub fn parse_flat_item(s: &str) -> Result<FlatItem> {
+    if let Some(off) = s.strip_prefix('@') {
+        return Ok(FlatItem::At(parse_len(off)?));
+    }
+    if let Some(text) = s.strip_prefix("str:") {
+        return Ok(FlatItem::Bytes(unescape(text)?));
+    }
+    if let Some(h) = s.strip_prefix("hex:") {
+        return Ok(FlatItem::Bytes(parse_hex_str(h)?));
+    }
+    if let Some(n) = s.strip_prefix("fill:") {
+        return Ok(FlatItem::Fill(parse_len(n)?));
+    }
+    if let Some(rest) = s.strip_prefix("rep:") {
+        let (n, item) = rest.split_once(':').ok_or_else(|| anyhow!("expected rep:N:ITEM, got: {s}"))?;
+        let inner = parse_flat_item(item)?;
+        if matches!(inner, FlatItem::At(_)) {
+            bail!("placements cannot be repeated: {s}");
+        }
+        return Ok(FlatItem::Repeat(parse_len(n)?, Box::new(inner)));
+    }
+    Ok(FlatItem::Int(parse_item(s)?))
+}
+
+/// The built payload plus one labelled range per argument.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Flat {
+    pub bytes: Vec<u8>,
+    /// Byte range, index of the argument that produced it, and a label.
+    pub map: Vec<(Range<usize>, usize, String)>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FlatOpts {
+    pub width: Width,
+    pub endian: Endian,
+    pub strict: bool,
+    pub filler: u8,
+}
+
+fn emit(item: &FlatItem, opts: &FlatOpts, out: &mut Vec<u8>) -> Result<()> {
+    match item {
+        FlatItem::Int(it) => {
+            let (width, signed) = it.ty.unwrap_or((opts.width, false));
+            let b = try_pack(it.value, width, opts.endian, signed, opts.strict)?;
+            grow(out.len(), b.len())?;
+            out.extend_from_slice(&b);
+        }
+        FlatItem::Bytes(b) => {
+            grow(out.len(), b.len())?;
+            out.extend_from_slice(b);
+        }
+        FlatItem::Fill(n) => out.resize(grow(out.len(), *n)?, opts.filler),
+        FlatItem::Repeat(n, inner) => {
+            let mut one = Vec::new();
+            emit(inner, opts, &mut one)?;
+            let total = one.len().checked_mul(*n).ok_or_else(|| anyhow!("repeat count {n} is too large"))?;
+            grow(out.len(), total)?;
+            out.extend(one.iter().cycle().take(total));
+        }
+        FlatItem::At(_) => unreachable!("placements are handled by build_flat"),
+    }
+    Ok(())
+}
+
+pub fn build_flat(args: &[String], opts: &FlatOpts) -> Result<Flat> {
+    let mut flat = Flat::default();
+    for (i, arg) in args.iter().enumerate() {
+        let item = parse_flat_item(arg).map_err(|e| anyhow!("item {i} ({arg}): {e}"))?;
+        let start = flat.bytes.len();
+        match item {
+            FlatItem::At(off) => {
+                if off < start {
+                    let (r, j, label) = flat
+                        .map
+                        .iter()
+                        .find(|(r, _, _)| r.end > off)
+                        .cloned()
+                        .unwrap_or((start..start, i, String::new()));
+                    bail!(
+                        "item {i} ({arg}): placement at 0x{off:x} overlaps item {j} ({label}) at 0x{:x}..0x{:x}",
+                        r.start, r.end
+                    );
+                }
+                flat.bytes.resize(off, opts.filler);
+                if off > start {
+                    flat.map.push((start..off, i, format!("pad to {arg}")));
+                }
+            }
+            _ => {
+                emit(&item, opts, &mut flat.bytes).map_err(|e| anyhow!("item {i} ({arg}): {e}"))?;
+                flat.map.push((start..flat.bytes.len(), i, arg.clone()));
+            }
+        }
+    }
+    Ok(flat)
+}
+
+pub fn write_map(w: &mut dyn Write, flat: &Flat) -> Result<()> {
+    for (r, _, label) in &flat.map {
+        write_span(w, r.start, r.len(), label)?;
+    }
+    Ok(())
+}
+
+pub fn run_flat<W: Write>(
+    args: &[String],
+    opts: &FlatOpts,
+    fmt: &Formatter,
+    out: &mut W,
+    map: Option<&mut dyn Write>,
+) -> Result<()> {
+    let flat = build_flat(args, opts)?;
+    if let Some(m) = map {
+        write_map(m, &flat)?;
+    }
+    let origin = |off: usize| {
+        let (_, i, label) = flat.map.iter().find(|(r, _, _)| r.contains(&off))?;
+        Some(format!("item {i} ({label})"))
+    };
+    fmt.write_traced(out, &flat.bytes, &origin)
+}
@@ -0,0 +1,244 @@
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, ValueEnum};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::ops::Range;
+use crate::api::{parse_item, Formatter, Item};
+use crate::cli::{endian_from, parse_byte, parse_width, BadcharArgs, OutFmtArg};
+use crate::util::{parse_int, try_pack, try_unpack, write_span, Endian, Width};
+
+#[derive(Args)]
+pub struct FmtstrArgs {
+    /// printf argument index where the payload starts on the stack.
+    #[arg(long)]
+    pub offset: usize,
+    /// Pointer width; also the default width of written values.
+    #[arg(long, value_parser = parse_width, default_value = "64")]
+    pub width: Width,
+    /// ADDR=VALUE, with an optional type such as ADDR=u16:VALUE.
+    #[arg(long = "write", required = true)]
+    pub writes: Vec<String>,
+    /// Bytes stored per %n: byte (%hhn), short (%hn) or int (%n).
+    #[arg(long, value_enum, default_value_t = WriteSize::Byte)]
+    pub size: WriteSize,
+    /// Characters already printed before the payload.
+    #[arg(long, default_value_t = 0)]
+    pub written: usize,
+    /// Put the addresses before the format string.
+    #[arg(long)]
+    pub addr_first: bool,
+    #[arg(long, value_parser = parse_byte, default_value = "0x61")]
+    pub pad_byte: u8,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// Print the offset of the format, padding and each address to stderr.
+    #[arg(long)]
+    pub explain: bool,
+}
+
+impl FmtstrArgs {
+    pub fn opts(&self) -> FmtOpts {
+        FmtOpts {
+            offset: self.offset,
+            width: self.width,
+            endian: endian_from(self.be, self.le),
+            size: self.size,
+            written: self.written,
+            addr_first: self.addr_first,
+            pad: self.pad_byte,
+        }
+    }
+}
+
+/// How many bytes each `%n` conversion stores.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum WriteSize {
+    Byte,  // %hhn
+    Short, // %hn
+    Int,   // %n
+}
+
+impl WriteSize {
+    pub fn bytes(self) -> usize {
+        match self {
+            WriteSize::Byte => 1,
+            WriteSize::Short => 2,
+            WriteSize::Int => 4,
+        }
+    }
+
+    fn spec(self) -> &'static str {
+        match self {
+            WriteSize::Byte => "hhn",
+            WriteSize::Short => "hn",
+            WriteSize::Int => "n",
+        }
+    }
+}
+
+/// One `ADDR=VALUE` write; the value may be typed, e.g. `0x601018=u16:0x4142`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FmtWrite {
+    pub addr: i128,
+    pub value: Item,
+}
+
+pub fn parse_write(s: &str) -> Result<FmtWrite> {
+    let (addr, value) = s.split_once('=').ok_or_else(|| anyhow!("expected ADDR=VALUE, got: {s}"))?;
+    Ok(FmtWrite { addr: parse_int(addr)?, value: parse_item(value)? })
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FmtOpts {
+    /// printf argument index at which the payload starts on the stack.
+    pub offset: usize,
+    /// Pointer width, also the default width of values.
+    pub width: Width,
+    pub endian: Endian,
+    pub size: WriteSize,
+    /// Characters printed before the payload is reached.
+    pub written: usize,
+    /// Put addresses before the format string instead of after it.
+    pub addr_first: bool,
+    /// Byte used to align the addresses to a stack slot.
+    pub pad: u8,
+}
+
+/// The payload plus a labelled range for the format, padding and each address.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FmtPayload {
+    pub bytes: Vec<u8>,
+    pub map: Vec<(Range<usize>, String)>,
+}
+
+struct Chunk {
+    addr: i128,
+    value: u64,
+    write: usize,
+}
+
+/// Split every write into `size`-byte stores, smallest value first so the
+/// printed-character count only has to grow.
+fn split(writes: &[FmtWrite], opts: &FmtOpts) -> Result<Vec<Chunk>> {
+    let n = opts.size.bytes();
+    let part_width = Width::new(8 * n as u32)?;
+    let mut chunks = Vec::new();
+    for (i, w) in writes.iter().enumerate() {
+        let (width, signed) = w.value.ty.unwrap_or((opts.width, false));
+        let mem = try_pack(w.value.value, width, opts.endian, signed, true).map_err(|e| anyhow!("write {i}: {e}"))?;
+        if mem.len() % n != 0 {
+            bail!("write {i}: a {}-byte value cannot be split into {n}-byte writes", mem.len());
+        }
+        for (k, part) in mem.chunks(n).enumerate() {
+            let value = try_unpack(part, part_width, opts.endian, false)? as u64;
+            chunks.push(Chunk { addr: w.addr + (k * n) as i128, value, write: i });
+        }
+    }
+    chunks.sort_by_key(|c| c.value);
+    Ok(chunks)
+}
+
+fn format(chunks: &[Chunk], first_arg: usize, printed: usize, size: WriteSize) -> String {
+    let modulus = 1u64 << (8 * size.bytes());
+    let mut count = printed as u64;
+    let mut f = String::new();
+    for (j, c) in chunks.iter().enumerate() {
+        let need = (c.value + modulus - count % modulus) % modulus;
+        if need > 0 {
+            let _ = write!(f, "%{need}c");
+            count += need;
+        }
+        let _ = write!(f, "%{}${}", first_arg + j, size.spec());
+    }
+    f
+}
+
+pub fn build_fmtstr(writes: &[FmtWrite], opts: &FmtOpts) -> Result<FmtPayload> {
+    let chunks = split(writes, opts)?;
+    let mut addrs = Vec::new();
+    let mut addr_map = Vec::new();
+    for c in &chunks {
+        let start = addrs.len();
+        let packed = try_pack(c.addr, opts.width, opts.endian, false, true)
+            .map_err(|e| anyhow!("write {}: address: {e}", c.write))?;
+        addrs.extend_from_slice(&packed);
+        addr_map.push((start..addrs.len(), format!("address of write {} (0x{:x})", c.write, c.addr)));
+    }
+
+    let mut p = FmtPayload::default();
+    if opts.addr_first {
+        let fmt = format(&chunks, opts.offset, opts.written + addrs.len(), opts.size);
+        p.bytes = addrs;
+        p.map = addr_map;
+        p.map.push((p.bytes.len()..p.bytes.len() + fmt.len(), "format".into()));
+        p.bytes.extend_from_slice(fmt.as_bytes());
+        return Ok(p);
+    }
+
+    // Addresses follow the format, so their argument indices depend on its
+    // length; grow the guess until the format fits before them.
+    let slot = opts.width.bytes();
+    let mut first = opts.offset;
+    let fmt = loop {
+        let fmt = format(&chunks, first, opts.written, opts.size);
+        let needed = opts.offset + fmt.len().div_ceil(slot);
+        if needed <= first {
+            break fmt;
+        }
+        first = needed;
+    };
+    p.bytes.extend_from_slice(fmt.as_bytes());
+    p.map.push((0..fmt.len(), "format".into()));
+    let base = (first - opts.offset) * slot;
+    if base > fmt.len() {
+        p.bytes.resize(base, opts.pad);
+        p.map.push((fmt.len()..base, "padding".into()));
+    }
+    p.bytes.extend_from_slice(&addrs);
+    p.map.extend(addr_map.into_iter().map(|(r, l)| (r.start + base..r.end + base, l)));
+    Ok(p)
+}
+
+pub fn write_map(w: &mut dyn Write, p: &FmtPayload) -> Result<()> {
+    for (r, label) in &p.map {
+        write_span(w, r.start, r.len(), label)?;
+    }
+    Ok(())
+}
+
+pub fn run_fmtstr<W: Write>(
+    writes: &[String],
+    opts: &FmtOpts,
+    fmt: &Formatter,
+    out: &mut W,
+    explain: Option<&mut dyn Write>,
+) -> Result<()> {
+    let writes = writes
+        .iter()
+        .enumerate()
+        .map(|(i, w)| parse_write(w).map_err(|e| anyhow!("write {i} ({w}): {e}")))
+        .collect::<Result<Vec<_>>>()?;
+    let mut opts = *opts;
+    if !fmt.allows(opts.pad) {
+        // Any printable byte works as padding; take the first one allowed.
+        if let Some(b) = (b'a'..=b'z').chain(b'A'..=b'Z').find(|&b| fmt.allows(b)) {
+            opts.pad = b;
+        }
+    }
+    let payload = build_fmtstr(&writes, &opts)?;
+    if let Some(e) = explain {
+        write_map(e, &payload)?;
+    }
+    let origin = |off: usize| payload.map.iter().find(|(r, _)| r.contains(&off)).map(|(_, l)| l.clone());
+    fmt.write_traced(out, &payload.bytes, &origin)
+}
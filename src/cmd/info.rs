@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::Args;
+use std::io::Write;
+use crate::util::{parse_int, try_pack, unpack_scalar, Endian, Width};
+
+#[derive(Args)]
+pub struct InfoArgs {
+    #[arg(allow_negative_numbers = true)]
+    pub value: String,
+}
+
+const WIDTHS: [Width; 4] = [Width::W8, Width::W16, Width::W32, Width::W64];
+
+/// The two's-complement bit pattern of `v`: 64 bits for negative values
+/// that fit in i64, 128 bits otherwise.
+fn pattern(v: i128) -> u128 {
+    if v < 0 && v >= i64::MIN as i128 { v as u64 as u128 } else { v as u128 }
+}
+
+/// The smallest width that holds `v` as either a signed or unsigned integer.
+fn min_width(v: i128) -> Width {
+    let fits = |w: &Width| {
+        let bits = w.bits();
+        v >= -(1i128 << (bits - 1)) && v < (1i128 << bits)
+    };
+    WIDTHS.into_iter().find(fits).unwrap_or(Width::W128)
+}
+
+fn ascii(b: &[u8]) -> String {
+    b.iter().map(|&c| if (0x20..=0x7e).contains(&c) { c as char } else { '.' }).collect()
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|x| format!("{x:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+/// Every common reading of one integer, rax2-style.
+pub fn run_info<W: Write>(value: &str, out: &mut W) -> Result<()> {
+    let v = parse_int(value)?;
+    let bits = pattern(v);
+    let w = min_width(v);
+    let le = try_pack(v, w, Endian::Little, true, false)?;
+    let be = try_pack(v, w, Endian::Big, true, false)?;
+
+    writeln!(out, "dec       {v}")?;
+    writeln!(out, "hex       0x{bits:x}")?;
+    writeln!(out, "oct       0o{bits:o}")?;
+    writeln!(out, "bin       0b{bits:b}")?;
+    writeln!(out, "le        {}", hex(&le))?;
+    writeln!(out, "be        {}", hex(&be))?;
+    writeln!(out, "ascii     le \"{}\"  be \"{}\"", ascii(&le), ascii(&be))?;
+    writeln!(out, "f32       {:?}", f32::from_bits(bits as u32))?;
+    writeln!(out, "f64       {:?}", f64::from_bits(bits as u64))?;
+    writeln!(out, "popcount  {}", bits.count_ones())?;
+    writeln!(out, "bitlen    {}", 128 - bits.leading_zeros())?;
+    writeln!(out)?;
+    writeln!(out, "width  {:<22}  signed", "unsigned")?;
+    for w in WIDTHS {
+        // Non-strict packing masks to the width, like a C cast would.
+        let b = try_pack(v, w, Endian::Little, true, false)?;
+        let u = unpack_scalar(&b, w.bits(), Endian::Little, false)?;
+        let s = unpack_scalar(&b, w.bits(), Endian::Little, true)?;
+        writeln!(out, "{:<5}  {u:<22}  {s}", w.bits())?;
+    }
+    Ok(())
+}
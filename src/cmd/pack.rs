@@ -1,34 +1,37 @@
 use anyhow::Result;
-use crate::util::{pack_scalar, write_bytes, Endian, OutFmt};
+use std::io::Write;
+use crate::api::{Formatter, Packed, Packer};
+use crate::util::write_span;
 
-#[allow(clippy::too_many_arguments)]
-pub fn run_pack(
-    width_bits: u32,
+pub fn run_pack<W: Write>(
+    packer: &Packer,
     values: &[String],
-    endian: Endian,
-    signed: bool,
-    outfmt: OutFmt,
-    sep: &str,
-    uppercase: bool,
-    strict: bool,
-    repeat: Option<usize>,
+    fmt: &Formatter,
+    out: &mut W,
+    explain: Option<&mut dyn Write>,
 ) -> Result<()> {
-    let mut buf = Vec::new();
-
-    for v in values {
-        let n = crate::util::parse_int(v)?;
-        let b = pack_scalar(n, width_bits, endian, signed, strict)?;
-        buf.extend_from_slice(&b);
-    }
-    
-    if let Some(times) = repeat {
-        if times > 1 && !buf.is_empty() {
-            let pattern = buf.clone();
-            for _ in 1..times {
-                buf.extend_from_slice(&pattern);
-            }
-        }
+    let packed = packer.pack_layout(values)?;
+    if let Some(e) = explain {
+        write_explain(e, &packed)?;
     }
+    let origin = |off: usize| match packed.values.iter().find(|(_, r)| r.contains(&off)) {
+        Some((i, _)) => Some(format!("value {i} ({})", values.get(*i)?)),
+        None => packed.padding.iter().any(|r| r.contains(&off)).then(|| "padding".to_string()),
+    };
+    fmt.write_traced(out, &packed.bytes, &origin)
+}
 
-    write_bytes(outfmt, &buf, sep, uppercase)
+/// One line per value or padding run: offset, length, what it is.
+pub fn write_explain(w: &mut dyn Write, packed: &Packed) -> Result<()> {
+    let mut parts: Vec<(usize, usize, String)> = packed
+        .values
+        .iter()
+        .map(|(i, r)| (r.start, r.len(), format!("value {i}")))
+        .chain(packed.padding.iter().map(|r| (r.start, r.len(), "padding".to_string())))
+        .collect();
+    parts.sort_by_key(|p| p.0);
+    for (off, len, what) in parts {
+        write_span(w, off, len, &what)?;
+    }
+    Ok(())
 }
@@ -0,0 +1,106 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::cli::{parse_byte, parse_size, ByteIoArgs};
+use crate::util::{read_input, InFmt, MAX_OUTPUT};
+
+#[derive(Args)]
+pub struct PadArgs {
+    /// Pad the input to exactly N bytes. Accepts + - * arithmetic.
+    #[arg(long, value_parser = parse_size)]
+    pub to: Option<usize>,
+    /// Pad the size up to a multiple of N bytes.
+    #[arg(long, value_parser = parse_size)]
+    pub align: Option<usize>,
+    /// Padding byte.
+    #[arg(long, value_parser = parse_byte, default_value = "0")]
+    pub with: u8,
+    /// Input first, padding after (the default).
+    #[arg(long, conflicts_with = "rjust")]
+    pub ljust: bool,
+    /// Padding first, input after.
+    #[arg(long)]
+    pub rjust: bool,
+    /// Keep only the first N bytes of input longer than --to.
+    #[arg(long, requires = "to")]
+    pub truncate: bool,
+    #[command(flatten)]
+    pub io: ByteIoArgs,
+}
+
+impl PadArgs {
+    pub fn opts(&self) -> PadOpts {
+        PadOpts {
+            to: self.to,
+            align: self.align,
+            with: self.with,
+            justify: if self.rjust { Justify::Right } else { Justify::Left },
+            truncate: self.truncate,
+        }
+    }
+}
+
+/// Which side of the padding the input goes on.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Justify {
+    /// Data first, padding after.
+    #[default]
+    Left,
+    /// Padding first, data after.
+    Right,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PadOpts {
+    /// Exact output size.
+    pub to: Option<usize>,
+    /// Round the output size up to a multiple of this, after `to`.
+    pub align: Option<usize>,
+    pub with: u8,
+    pub justify: Justify,
+    /// Cut input longer than `to` down to its first `to` bytes instead of failing.
+    pub truncate: bool,
+}
+
+pub fn pad_bytes(data: &[u8], opts: &PadOpts) -> Result<Vec<u8>> {
+    let mut data = data;
+    let mut size = data.len();
+    if let Some(to) = opts.to {
+        if data.len() > to {
+            if !opts.truncate {
+                bail!("input is {} bytes, longer than --to {to} (use --truncate to cut it)", data.len());
+            }
+            data = &data[..to];
+        }
+        size = to;
+    }
+    if let Some(align) = opts.align {
+        if align == 0 {
+            bail!("alignment must be at least 1");
+        }
+        size = size
+            .div_ceil(align)
+            .checked_mul(align)
+            .ok_or_else(|| anyhow!("aligning {size} bytes to {align} overflows"))?;
+    }
+    if size > data.len().max(MAX_OUTPUT) {
+        bail!("padded size {size} exceeds the {MAX_OUTPUT}-byte limit");
+    }
+    let fill = vec![opts.with; size - data.len()];
+    Ok(match opts.justify {
+        Justify::Left => [data, &fill].concat(),
+        Justify::Right => [&fill, data].concat(),
+    })
+}
+
+pub fn run_pad<R: Read, W: Write>(
+    opts: &PadOpts,
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    fmt.write(out, &pad_bytes(&data, opts)?)
+}
@@ -0,0 +1,109 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use crate::api::BadChars;
+use crate::cli::{endian_from, parse_u64, BadcharArgs, PackedValueArgs};
+use crate::util::parse_hex_str;
+
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("data").required(true).args(["p8", "p16", "p32", "p64", "p128", "bytes", "from_file"]))]
+pub struct PatchArgs {
+    /// File to patch in place (or copy, with -o).
+    pub file: PathBuf,
+    /// Offset of the first byte to overwrite.
+    #[arg(long, value_parser = parse_u64)]
+    pub at: u64,
+    #[command(flatten)]
+    pub value: PackedValueArgs,
+    /// Raw bytes as hex, e.g. "de ad".
+    #[arg(long)]
+    pub bytes: Option<String>,
+    /// Take the new bytes from a file.
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+    /// Refuse to patch unless these bytes (hex) are currently at the offset.
+    #[arg(long)]
+    pub expect: Option<String>,
+    /// Write the patched file here and leave the original untouched.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long)]
+    pub strict: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+}
+
+impl PatchArgs {
+    /// The replacement bytes selected by --pN, --bytes or --from-file.
+    pub fn new_bytes(&self) -> Result<Vec<u8>> {
+        if let Some(b) = self.value.packed(endian_from(self.be, self.le), self.strict)? {
+            return Ok(b);
+        }
+        match (&self.bytes, &self.from_file) {
+            (Some(h), _) => Ok(parse_hex_str(h)?),
+            (_, Some(p)) => Ok(fs::read(p)?),
+            _ => unreachable!("clap requires one of the data arguments"),
+        }
+    }
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|x| format!("{x:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+/// Whether `a` and `b` name the same existing file.
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Overwrite `new.len()` bytes of `path` at offset `at`, either in place or
+/// in a copy at `output`. With `expect`, the bytes currently at `at` must
+/// match it or nothing is written. Patches never grow the file, and new
+/// bytes are checked against `badchars` before anything is touched.
+pub fn run_patch(
+    path: &Path,
+    at: u64,
+    new: &[u8],
+    expect: Option<&[u8]>,
+    output: Option<&Path>,
+    badchars: Option<&BadChars>,
+) -> Result<()> {
+    let mut src = File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    let size = src.metadata()?.len();
+    let len = new.len().max(expect.map_or(0, <[u8]>::len)) as u64;
+    if at.checked_add(len).is_none_or(|end| end > size) {
+        bail!("patch of {len} byte(s) at 0x{at:x} runs past the end of {} ({size} bytes)", path.display());
+    }
+    if let Some(bc) = badchars {
+        bc.check(new, &|off| Some(format!("file offset 0x{:x}", at + off as u64)))?;
+    }
+    if let Some(want) = expect {
+        let mut got = vec![0; want.len()];
+        src.seek(SeekFrom::Start(at))?;
+        src.read_exact(&mut got)?;
+        if got != want {
+            bail!("refusing to patch: expected {} at 0x{at:x}, found {}", hex(want), hex(&got));
+        }
+    }
+    drop(src);
+
+    let target = match output {
+        // Copying a file onto itself would truncate it first.
+        Some(out) if same_file(path, out) => path,
+        Some(out) => {
+            fs::copy(path, out).with_context(|| format!("cannot copy to {}", out.display()))?;
+            out
+        }
+        None => path,
+    };
+    let mut f = OpenOptions::new().write(true).open(target).with_context(|| format!("cannot open {}", target.display()))?;
+    f.seek(SeekFrom::Start(at))?;
+    f.write_all(new)?;
+    Ok(())
+}
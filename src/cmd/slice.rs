@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use crate::api::Formatter;
+use crate::cli::{parse_expr, ByteIoArgs};
+use crate::util::{read_input, InFmt};
+
+#[derive(Args)]
+pub struct SliceArgs {
+    /// Input file; reads stdin when omitted.
+    pub file: Option<PathBuf>,
+    /// First byte; negative counts back from the end. Accepts + - * arithmetic.
+    #[arg(long, value_parser = parse_expr, allow_hyphen_values = true, default_value = "0")]
+    pub offset: i128,
+    /// Number of bytes to take.
+    #[arg(long, value_parser = parse_expr, allow_hyphen_values = true, conflicts_with = "end")]
+    pub len: Option<i128>,
+    /// Exclusive end offset; negative counts back from the end.
+    #[arg(long, value_parser = parse_expr, allow_hyphen_values = true)]
+    pub end: Option<i128>,
+    #[command(flatten)]
+    pub io: ByteIoArgs,
+}
+
+impl SliceArgs {
+    pub fn span(&self) -> Span {
+        match (self.len, self.end) {
+            (Some(n), _) => Span::Len(n),
+            (_, Some(e)) => Span::End(e),
+            _ => Span::ToEof,
+        }
+    }
+}
+
+/// Where a slice stops.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Span {
+    ToEof,
+    Len(i128),
+    /// Exclusive end offset; negative counts back from the end of the input.
+    End(i128),
+}
+
+/// Resolve `offset` (negative counts back from EOF) and `span` against an
+/// input of `len` bytes. Ranges that leave the input are errors.
+pub fn resolve_range(len: usize, offset: i128, span: Span) -> Result<Range<usize>> {
+    let abs = |v: i128| if v < 0 { len as i128 + v } else { v };
+    let start = abs(offset);
+    if !(0..=len as i128).contains(&start) {
+        bail!("offset {offset} is outside the input ({len} bytes)");
+    }
+    let end = match span {
+        Span::ToEof => len as i128,
+        Span::Len(n) if n < 0 => bail!("length must not be negative: {n}"),
+        Span::Len(n) => match start.checked_add(n) {
+            Some(end) => end,
+            None => bail!("range at 0x{start:x} with length 0x{n:x} runs past the end of the input ({len} bytes)"),
+        },
+        Span::End(e) => abs(e),
+    };
+    if end < start {
+        bail!("end 0x{end:x} is before offset 0x{start:x}");
+    }
+    if end > len as i128 {
+        bail!("range 0x{start:x}..0x{end:x} runs past the end of the input ({len} bytes)");
+    }
+    Ok(start as usize..end as usize)
+}
+
+pub fn run_slice<R: Read, W: Write>(
+    offset: i128,
+    span: Span,
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    let r = resolve_range(data.len(), offset, span)?;
+    fmt.write(out, &data[r])
+}
@@ -0,0 +1,98 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::checksum::{Algo, CrcParams};
+use crate::cli::{parse_u64, BadcharArgs, InFmtArg, OutFmtArg};
+use crate::util::{read_input, try_pack, Endian, InFmt, Width};
+
+#[derive(Args)]
+pub struct SumArgs {
+    /// crc8, crc16-ccitt, crc16-modbus, crc32, crc32c, adler32, fletcher16, fletcher32 or inet.
+    #[arg(long, value_parser = parse_algo)]
+    pub algo: Option<Algo>,
+    /// Width in bits of a custom CRC; required with --poly unless --algo names a CRC.
+    #[arg(long)]
+    pub crc_width: Option<u32>,
+    #[arg(long, value_parser = parse_u64)]
+    pub poly: Option<u64>,
+    #[arg(long, value_parser = parse_u64)]
+    pub init: Option<u64>,
+    #[arg(long)]
+    pub refin: Option<bool>,
+    #[arg(long)]
+    pub refout: Option<bool>,
+    #[arg(long, value_parser = parse_u64)]
+    pub xorout: Option<u64>,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Hex)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+}
+
+impl SumArgs {
+    /// The named algorithm, with any CRC parameters given on the command line applied.
+    pub fn algo(&self) -> Result<Algo> {
+        let custom = self.crc_width.is_some()
+            || self.poly.is_some()
+            || self.init.is_some()
+            || self.refin.is_some()
+            || self.refout.is_some()
+            || self.xorout.is_some();
+        let mut p = match self.algo {
+            Some(Algo::Crc(p)) => p,
+            Some(algo) if !custom => return Ok(algo),
+            Some(_) => bail!("CRC parameters only apply to CRC algorithms"),
+            None => {
+                let (Some(width), Some(poly)) = (self.crc_width, self.poly) else {
+                    bail!("give --algo, or --crc-width and --poly for a custom CRC");
+                };
+                CrcParams { width, poly, init: 0, refin: false, refout: false, xorout: 0 }
+            }
+        };
+        p.width = self.crc_width.unwrap_or(p.width);
+        p.poly = self.poly.unwrap_or(p.poly);
+        p.init = self.init.unwrap_or(p.init);
+        p.refin = self.refin.unwrap_or(p.refin);
+        p.refout = self.refout.unwrap_or(p.refout);
+        p.xorout = self.xorout.unwrap_or(p.xorout);
+        p.validate()?;
+        Ok(Algo::Crc(p))
+    }
+}
+
+fn parse_algo(s: &str) -> Result<Algo, String> {
+    Algo::named(s).ok_or_else(|| format!("unknown algorithm {s}; expected one of: {}", Algo::NAMES.join(", ")))
+}
+
+/// The checksum packed into the fewest whole bytes that hold it.
+pub fn sum_bytes(algo: &Algo, data: &[u8], endian: Endian) -> Result<Vec<u8>> {
+    let n = algo.bits().div_ceil(8) as usize;
+    let b = try_pack(algo.compute(data) as i128, Width::W64, endian, false, false)?;
+    Ok(match endian {
+        Endian::Little => b[..n].to_vec(),
+        Endian::Big => b[8 - n..].to_vec(),
+    })
+}
+
+pub fn run_sum<R: Read, W: Write>(
+    algo: &Algo,
+    endian: Endian,
+    infmt: InFmt,
+    fmt: &Formatter,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    fmt.write(out, &sum_bytes(algo, &data, endian)?)
+}
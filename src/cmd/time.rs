@@ -0,0 +1,84 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::cli::{BadcharArgs, InFmtArg, OutFmtArg};
+use crate::error::PakxError;
+use crate::time::{TimeFormat, Timestamp};
+use crate::util::{pack_scalar, read_input, unpack_scalar, Endian, InFmt};
+
+#[derive(Subcommand)]
+pub enum TimeCmd {
+    Pack(TimePackArgs),
+    Unpack(TimeUnpackArgs),
+}
+
+#[derive(Args)]
+pub struct TimePackArgs {
+    #[arg(long, value_enum)]
+    pub format: TimeFormat,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// ISO-8601 timestamps, e.g. 2024-05-01T12:00:00Z or 2024-05-01 14:00+02:00.
+    #[arg(required = true)]
+    pub values: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct TimeUnpackArgs {
+    #[arg(long, value_enum)]
+    pub format: TimeFormat,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+    #[arg(long, conflicts_with = "le")]
+    pub be: bool,
+    #[arg(long, conflicts_with = "be")]
+    pub le: bool,
+}
+
+pub fn run_time_pack<W: Write>(
+    values: &[String],
+    format: TimeFormat,
+    endian: Endian,
+    fmt: &Formatter,
+    out: &mut W,
+) -> Result<()> {
+    let (bits, signed) = format.int_type();
+    let mut bytes = Vec::new();
+    for (i, v) in values.iter().enumerate() {
+        let n = Timestamp::parse(v).and_then(|ts| format.encode(ts)).map_err(|e| anyhow!("value {i}: {e}"))?;
+        bytes.extend(pack_scalar(n, bits, endian, signed, true)?);
+    }
+    fmt.write(out, &bytes)
+}
+
+/// Decode every value in the input and print it as ISO-8601, one per line.
+pub fn run_time_unpack<R: Read, W: Write>(
+    format: TimeFormat,
+    endian: Endian,
+    infmt: InFmt,
+    input: R,
+    out: &mut W,
+) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    let (bits, signed) = format.int_type();
+    let n = bits as usize / 8;
+    for (i, chunk) in data.chunks(n).enumerate() {
+        if chunk.len() < n {
+            return Err(PakxError::Truncated { needed: n, got: chunk.len(), offset: i * n }.into());
+        }
+        let ts = format.decode(unpack_scalar(chunk, bits, endian, signed)?)?;
+        writeln!(out, "{}", ts.to_iso())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use std::io::{Read, Write};
+use crate::api::Formatter;
+use crate::cli::{BadcharArgs, InFmtArg, OutFmtArg};
+use crate::error::PakxError;
+use crate::util::{read_input, InFmt};
+
+#[derive(Subcommand)]
+pub enum UuidCmd {
+    Pack(UuidPackArgs),
+    Unpack(UuidUnpackArgs),
+}
+
+#[derive(Args)]
+pub struct UuidPackArgs {
+    /// Byte layout: rfc is all big-endian, ms stores the first three fields little-endian.
+    #[arg(long, value_enum, default_value_t = Layout::Rfc)]
+    pub layout: Layout,
+    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
+    pub out: OutFmtArg,
+    #[arg(long, default_value = " ")]
+    pub sep: String,
+    #[arg(long)]
+    pub uppercase: bool,
+    #[command(flatten)]
+    pub bad: BadcharArgs,
+    /// Print the version and variant of each UUID to stderr.
+    #[arg(long)]
+    pub explain: bool,
+    /// UUIDs as 8-4-4-4-12 hex, optionally in braces or prefixed with urn:uuid:.
+    #[arg(required = true)]
+    pub values: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct UuidUnpackArgs {
+    #[arg(long, value_enum, default_value_t = Layout::Rfc)]
+    pub layout: Layout,
+    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
+    pub r#in: InFmtArg,
+}
+
+/// How the 16 bytes of a UUID are laid out in memory.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Layout {
+    /// RFC 4122: every field big-endian, i.e. the bytes in text order.
+    Rfc,
+    /// Microsoft GUID: `Data1`, `Data2` and `Data3` little-endian, the last
+    /// eight bytes as written.
+    Ms,
+}
+
+/// A UUID held in RFC 4122 (text) byte order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Uuid(pub [u8; 16]);
+
+/// Reverse the first three fields in place; the swap is its own inverse.
+fn swap_fields(b: &mut [u8; 16]) {
+    b[0..4].reverse();
+    b[4..6].reverse();
+    b[6..8].reverse();
+}
+
+impl Uuid {
+    /// Canonical `8-4-4-4-12` text, 32 bare hex digits, or either wrapped in
+    /// `{}` or prefixed with `urn:uuid:`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let bad = || anyhow!("invalid UUID {s:?}: expected xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx");
+        let t = s.strip_prefix("urn:uuid:").unwrap_or(s);
+        let t = t.strip_prefix('{').and_then(|t| t.strip_suffix('}')).unwrap_or(t);
+        let hex: String = match t.len() {
+            32 => t.to_string(),
+            36 if [8, 13, 18, 23].iter().all(|&i| t.as_bytes()[i] == b'-') => t.replace('-', ""),
+            _ => return Err(bad()),
+        };
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(bad());
+        }
+        let mut b = [0; 16];
+        for (i, x) in b.iter_mut().enumerate() {
+            *x = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| bad())?;
+        }
+        Ok(Uuid(b))
+    }
+
+    pub fn to_bytes(&self, layout: Layout) -> [u8; 16] {
+        let mut b = self.0;
+        if layout == Layout::Ms {
+            swap_fields(&mut b);
+        }
+        b
+    }
+
+    pub fn from_bytes(bytes: [u8; 16], layout: Layout) -> Self {
+        let mut b = bytes;
+        if layout == Layout::Ms {
+            swap_fields(&mut b);
+        }
+        Uuid(b)
+    }
+
+    /// The variant named by the top bits of byte 8.
+    pub fn variant(&self) -> &'static str {
+        match self.0[8] {
+            0x00..=0x7f => "NCS",
+            0x80..=0xbf => "RFC 4122",
+            0xc0..=0xdf => "Microsoft",
+            _ => "reserved",
+        }
+    }
+
+    /// The version nibble; only meaningful for the RFC 4122 variant.
+    pub fn version(&self) -> Option<u8> {
+        (self.variant() == "RFC 4122").then_some(self.0[6] >> 4)
+    }
+
+    /// `version N, variant X`, or just the variant when it has no version.
+    pub fn describe(&self) -> String {
+        match self.version() {
+            Some(v) => format!("version {v}, variant {}", self.variant()),
+            None => format!("variant {}", self.variant()),
+        }
+    }
+}
+
+impl std::fmt::Display for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn run_uuid_pack<W: Write>(
+    values: &[String],
+    layout: Layout,
+    fmt: &Formatter,
+    out: &mut W,
+    explain: Option<&mut dyn Write>,
+) -> Result<()> {
+    let uuids = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| Uuid::parse(v).map_err(|e| anyhow!("value {i}: {e}")))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(e) = explain {
+        for (i, u) in uuids.iter().enumerate() {
+            writeln!(e, "value {i}: {u}  {}", u.describe())?;
+        }
+    }
+    let bytes: Vec<u8> = uuids.iter().flat_map(|u| u.to_bytes(layout)).collect();
+    fmt.write(out, &bytes)
+}
+
+/// Decode every 16 bytes of input and print the UUID with its version and
+/// variant, one per line.
+pub fn run_uuid_unpack<R: Read, W: Write>(layout: Layout, infmt: InFmt, input: R, out: &mut W) -> Result<()> {
+    let data = read_input(input, infmt)?;
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let b: [u8; 16] = chunk
+            .try_into()
+            .map_err(|_| PakxError::Truncated { needed: 16, got: chunk.len(), offset: i * 16 })?;
+        let u = Uuid::from_bytes(b, layout);
+        writeln!(out, "{u}  {}", u.describe())?;
+    }
+    Ok(())
+}
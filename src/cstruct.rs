@@ -0,0 +1,651 @@
+//! Struct layouts computed from C declarations.
+//!
+//! Supports the subset found in protocol and file-format headers: stdint and
+//! basic integer types, `float`/`double`, pointers, fixed arrays, nested and
+//! anonymous structs, `typedef struct` aliases, `#pragma pack` (including
+//! push/pop) and `__attribute__((packed))` / `__attribute__((aligned(N)))` on
+//! structs and members. Every scalar is aligned to its own size, as on x86-64
+//! (LP64) and ARM/RISC-V (ILP32).
+//! Unions and bit-fields are rejected.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+
+use crate::schema::{Count, Field, Kind, Schema};
+use crate::util::{Endian, Width};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Abi {
+    #[default]
+    Lp64,
+    Ilp32,
+}
+
+impl Abi {
+    fn long(self) -> usize {
+        match self {
+            Abi::Lp64 => 8,
+            Abi::Ilp32 => 4,
+        }
+    }
+
+    fn ptr(self) -> usize {
+        self.long()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CType {
+    Int { size: usize, signed: bool },
+    Float { size: usize },
+    Char,
+    Ptr,
+    Struct(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: CType,
+    /// The type as spelled in the declaration, for display.
+    pub spelled: String,
+    pub dims: Vec<usize>,
+    pub offset: usize,
+    pub size: usize,
+    pub align: usize,
+    /// From `__attribute__((aligned(N)))`; survives `packed` but not `#pragma pack`.
+    pub aligned: Option<usize>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Layout {
+    pub name: String,
+    pub size: usize,
+    pub align: usize,
+    pub members: Vec<Member>,
+}
+
+/// All struct layouts found in a header, in definition order.
+#[derive(Clone, Debug, Default)]
+pub struct Header {
+    pub abi: Abi,
+    pub layouts: Vec<Layout>,
+    /// Typedef names mapped to the struct tag they stand for.
+    pub aliases: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Ident(String),
+    Num(usize),
+    Punct(char),
+    Pack(PackOp),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PackOp {
+    Set(Option<usize>),
+    Push(Option<usize>),
+    Pop,
+}
+
+fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("//") {
+            rest = r.find('\n').map_or("", |i| &r[i..]);
+        } else if let Some(r) = rest.strip_prefix("/*") {
+            let end = r.find("*/").map_or(r.len(), |i| i + 2);
+            // Keep line structure so preprocessor lines stay intact.
+            out.extend(r[..end].chars().filter(|&c| c == '\n'));
+            out.push(' ');
+            rest = &r[end..];
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn parse_pragma(line: &str) -> Result<Option<PackOp>> {
+    let words: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(args) = words.strip_prefix("#pragmapack(").and_then(|r| r.strip_suffix(')')) else {
+        return Ok(None);
+    };
+    let num = |s: &str| s.parse::<usize>().map_err(|_| anyhow!("bad #pragma pack value: {s}"));
+    let op = match args.split(',').collect::<Vec<_>>().as_slice() {
+        [""] => PackOp::Set(None),
+        ["push"] => PackOp::Push(None),
+        ["push", n] => PackOp::Push(Some(num(n)?)),
+        ["pop"] => PackOp::Pop,
+        [n] => PackOp::Set(Some(num(n)?)),
+        _ => bail!("unsupported #pragma pack form: {}", line.trim()),
+    };
+    Ok(Some(op))
+}
+
+fn tokenize(src: &str) -> Result<Vec<Tok>> {
+    let mut toks = Vec::new();
+    for line in strip_comments(src).lines() {
+        if line.trim_start().starts_with('#') {
+            if let Some(op) = parse_pragma(line.trim())? {
+                toks.push(Tok::Pack(op));
+            }
+            continue;
+        }
+        let mut cs = line.char_indices().peekable();
+        while let Some(&(i, c)) = cs.peek() {
+            if c.is_whitespace() {
+                cs.next();
+            } else if c.is_ascii_alphanumeric() || c == '_' {
+                let mut end = i;
+                while let Some(&(j, d)) = cs.peek() {
+                    if !(d.is_ascii_alphanumeric() || d == '_') { break; }
+                    end = j + d.len_utf8();
+                    cs.next();
+                }
+                let word = &line[i..end];
+                if c.is_ascii_digit() {
+                    let n = crate::util::parse_int(word.trim_end_matches(['u', 'U', 'l', 'L']))?;
+                    toks.push(Tok::Num(usize::try_from(n).map_err(|_| anyhow!("bad number: {word}"))?));
+                } else {
+                    toks.push(Tok::Ident(word.to_string()));
+                }
+            } else {
+                toks.push(Tok::Punct(c));
+                cs.next();
+            }
+        }
+    }
+    Ok(toks)
+}
+
+struct Parser {
+    toks: Vec<Tok>,
+    pos: usize,
+    abi: Abi,
+    pack: Option<usize>,
+    pack_stack: Vec<Option<usize>>,
+    layouts: Vec<Layout>,
+    by_name: HashMap<String, usize>,
+    aliases: HashMap<String, String>,
+    anon: usize,
+}
+
+#[derive(Default)]
+struct Attrs {
+    packed: bool,
+    aligned: Option<usize>,
+}
+
+fn round_up(n: usize, align: usize) -> usize {
+    n.div_ceil(align) * align
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Tok> {
+        let t = self.toks.get(self.pos).cloned().ok_or_else(|| anyhow!("unexpected end of header"))?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Tok::Punct(c))
+    }
+
+    fn is_ident(&self, s: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(w)) if w == s)
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.next()? {
+            Tok::Punct(p) if p == c => Ok(()),
+            t => bail!("expected '{c}', found {}", show(&t)),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next()? {
+            Tok::Ident(w) => Ok(w),
+            t => bail!("expected a name, found {}", show(&t)),
+        }
+    }
+
+    fn num(&mut self) -> Result<usize> {
+        match self.next()? {
+            Tok::Num(n) => Ok(n),
+            t => bail!("expected a number, found {}", show(&t)),
+        }
+    }
+
+    fn apply_pack(&mut self, op: PackOp) -> Result<()> {
+        match op {
+            PackOp::Set(n) => self.pack = n,
+            PackOp::Push(n) => {
+                self.pack_stack.push(self.pack);
+                if n.is_some() { self.pack = n; }
+            }
+            PackOp::Pop => self.pack = self.pack_stack.pop().ok_or_else(|| anyhow!("#pragma pack(pop) without push"))?,
+        }
+        Ok(())
+    }
+
+    /// `__attribute__((packed, aligned(N)))`, possibly repeated.
+    fn attrs(&mut self, into: &mut Attrs) -> Result<()> {
+        while self.is_ident("__attribute__") {
+            self.pos += 1;
+            self.expect('(')?;
+            self.expect('(')?;
+            loop {
+                match self.next()? {
+                    Tok::Ident(w) if w == "packed" || w == "__packed__" => into.packed = true,
+                    Tok::Ident(w) if w == "aligned" || w == "__aligned__" => {
+                        self.expect('(')?;
+                        let n = self.num()?;
+                        if !n.is_power_of_two() {
+                            bail!("alignment must be a power of two, got {n}");
+                        }
+                        into.aligned = Some(into.aligned.unwrap_or(1).max(n));
+                        self.expect(')')?;
+                    }
+                    Tok::Ident(w) => bail!("unsupported attribute: {w}"),
+                    Tok::Punct(',') => {}
+                    Tok::Punct(')') => break,
+                    t => bail!("unexpected {} in __attribute__", show(&t)),
+                }
+            }
+            self.expect(')')?;
+        }
+        Ok(())
+    }
+
+    fn top(&mut self) -> Result<()> {
+        while let Some(t) = self.peek().cloned() {
+            match t {
+                Tok::Pack(op) => {
+                    self.pos += 1;
+                    self.apply_pack(op)?;
+                }
+                Tok::Ident(w) if w == "typedef" && self.toks.get(self.pos + 1) == Some(&Tok::Ident("struct".into())) => {
+                    self.pos += 2;
+                    let mut attrs = Attrs::default();
+                    self.attrs(&mut attrs)?;
+                    let tag = match self.peek() { Some(Tok::Ident(_)) => Some(self.ident()?), _ => None };
+                    if !self.is_punct('{') {
+                        // `typedef struct foo foo_t;` names a struct defined elsewhere.
+                        if let (Some(tag), Some(Tok::Ident(alias)), Some(Tok::Punct(';'))) =
+                            (&tag, self.peek().cloned(), self.toks.get(self.pos + 1))
+                        {
+                            self.aliases.insert(alias, tag.clone());
+                        }
+                        self.skip_statement();
+                        continue;
+                    }
+                    let pending = format!("__pending{}", self.layouts.len());
+                    let idx = self.struct_body(tag.clone().unwrap_or(pending), Attrs::default())?;
+                    self.attrs(&mut attrs)?;
+                    self.relayout(idx, &attrs);
+                    let alias = self.ident()?;
+                    self.expect(';')?;
+                    match tag {
+                        None => {
+                            self.layouts[idx].name = alias.clone();
+                            self.by_name.insert(alias, idx);
+                        }
+                        Some(tag) if tag != alias => {
+                            self.aliases.insert(alias, tag);
+                        }
+                        Some(_) => {}
+                    }
+                }
+                Tok::Ident(w) if w == "struct" => {
+                    self.pos += 1;
+                    let mut attrs = Attrs::default();
+                    self.attrs(&mut attrs)?;
+                    let tag = self.ident()?;
+                    if !self.is_punct('{') {
+                        self.skip_statement();
+                        continue;
+                    }
+                    let idx = self.struct_body(tag, Attrs::default())?;
+                    self.attrs(&mut attrs)?;
+                    self.relayout(idx, &attrs);
+                    self.expect(';')?;
+                }
+                _ => self.skip_statement(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip a declaration we do not model (prototypes, enums, variables).
+    fn skip_statement(&mut self) {
+        let mut depth = 0usize;
+        while let Some(t) = self.peek().cloned() {
+            self.pos += 1;
+            match t {
+                Tok::Punct('{') => depth += 1,
+                Tok::Punct('}') => depth = depth.saturating_sub(1),
+                Tok::Punct(';') if depth == 0 => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse `{ members }` and register the layout under `name`.
+    fn struct_body(&mut self, name: String, attrs: Attrs) -> Result<usize> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        while !self.is_punct('}') {
+            if let Some(Tok::Pack(op)) = self.peek().cloned() {
+                self.pos += 1;
+                self.apply_pack(op)?;
+                continue;
+            }
+            self.member(&mut members)?;
+        }
+        self.expect('}')?;
+
+        let idx = self.layouts.len();
+        self.layouts.push(Layout { name: name.clone(), size: 0, align: 1, members });
+        self.by_name.insert(name, idx);
+        self.relayout(idx, &attrs);
+        Ok(idx)
+    }
+
+    /// A struct by tag or typedef name.
+    fn lookup(&self, name: &str) -> Option<usize> {
+        let tag = self.aliases.get(name).map_or(name, String::as_str);
+        self.by_name.get(tag).copied()
+    }
+
+    /// Assign offsets given the pack setting in effect and any attributes.
+    fn relayout(&mut self, idx: usize, attrs: &Attrs) {
+        let pack = self.pack.unwrap_or(usize::MAX);
+        let layout = &mut self.layouts[idx];
+        let mut off = 0usize;
+        let mut align = 1usize;
+        for m in &mut layout.members {
+            let explicit = m.aligned.unwrap_or(1);
+            let natural = if attrs.packed { 1 } else { m.align };
+            let a = natural.max(explicit).min(pack).max(1);
+            off = round_up(off, a);
+            m.offset = off;
+            off += m.size;
+            align = align.max(a);
+        }
+        if let Some(n) = attrs.aligned {
+            align = align.max(n);
+        }
+        layout.align = align;
+        layout.size = round_up(off, align);
+    }
+
+    fn base_type(&mut self) -> Result<(CType, String, usize, usize)> {
+        while self.is_ident("const") || self.is_ident("volatile") {
+            self.pos += 1;
+        }
+
+        if self.is_ident("union") {
+            bail!("unions are not supported");
+        }
+        if self.is_ident("struct") {
+            self.pos += 1;
+            let mut attrs = Attrs::default();
+            self.attrs(&mut attrs)?;
+            let tag = match self.peek() { Some(Tok::Ident(_)) => Some(self.ident()?), _ => None };
+            let name = if self.is_punct('{') {
+                let name = tag.unwrap_or_else(|| {
+                    self.anon += 1;
+                    format!("anon{}", self.anon)
+                });
+                let idx = self.struct_body(name, Attrs::default())?;
+                self.attrs(&mut attrs)?;
+                self.relayout(idx, &attrs);
+                self.layouts[idx].name.clone()
+            } else {
+                tag.ok_or_else(|| anyhow!("expected a struct name or body"))?
+            };
+            return self.struct_type(&name);
+        }
+
+        let mut words = Vec::new();
+        while let Some(Tok::Ident(w)) = self.peek() {
+            let known = matches!(
+                w.as_str(),
+                "unsigned" | "signed" | "char" | "short" | "int" | "long" | "float" | "double" | "void" | "_Bool" | "bool"
+            );
+            if !known && !words.is_empty() {
+                break;
+            }
+            let w = w.clone();
+            self.pos += 1;
+            let single = !known;
+            words.push(w);
+            if single {
+                break;
+            }
+        }
+        let spelled = words.join(" ");
+        let abi = self.abi;
+        let int = |size: usize, signed: bool| Ok((CType::Int { size, signed }, spelled.clone(), size, size));
+
+        let unsigned = words.iter().any(|w| w == "unsigned");
+        let longs = words.iter().filter(|w| *w == "long").count();
+        let has = |w: &str| words.iter().any(|x| x == w);
+        match spelled.as_str() {
+            "" => bail!("expected a type"),
+            "char" => Ok((CType::Char, spelled.clone(), 1, 1)),
+            "float" => Ok((CType::Float { size: 4 }, spelled.clone(), 4, 4)),
+            "double" => Ok((CType::Float { size: 8 }, spelled.clone(), 8, 8)),
+            "_Bool" | "bool" => int(1, false),
+            "uint8_t" | "int8_t" | "uint16_t" | "int16_t" | "uint32_t" | "int32_t" | "uint64_t" | "int64_t" => {
+                let bits: usize = spelled.trim_start_matches('u').trim_start_matches("int").trim_end_matches("_t").parse()?;
+                int(bits / 8, !spelled.starts_with('u'))
+            }
+            "size_t" | "uintptr_t" => int(abi.ptr(), false),
+            "ssize_t" | "intptr_t" | "ptrdiff_t" => int(abi.ptr(), true),
+            "void" => Ok((CType::Struct(String::new()), spelled.clone(), 0, 1)),
+            _ if has("double") => bail!("long double is not supported"),
+            _ if has("char") => int(1, !unsigned),
+            _ if has("short") => int(2, !unsigned),
+            _ if longs >= 2 => int(8, !unsigned),
+            _ if longs == 1 => int(abi.long(), !unsigned),
+            _ if has("int") || has("unsigned") || has("signed") => int(4, !unsigned),
+            other => match self.lookup(other) {
+                Some(_) => self.struct_type(other),
+                None => bail!("unknown type: {other}"),
+            },
+        }
+    }
+
+    fn struct_type(&self, name: &str) -> Result<(CType, String, usize, usize)> {
+        let idx = self.lookup(name).ok_or_else(|| anyhow!("incomplete struct type: {name}"))?;
+        let l = &self.layouts[idx];
+        Ok((CType::Struct(l.name.clone()), format!("struct {}", l.name), l.size, l.align))
+    }
+
+    fn member(&mut self, members: &mut Vec<Member>) -> Result<()> {
+        let (ty, spelled, size, align) = self.base_type()?;
+        loop {
+            let mut ptr = false;
+            while self.is_punct('*') {
+                self.pos += 1;
+                ptr = true;
+                while self.is_ident("const") || self.is_ident("volatile") {
+                    self.pos += 1;
+                }
+            }
+            let name = self.ident()?;
+            let mut dims = Vec::new();
+            while self.is_punct('[') {
+                self.pos += 1;
+                dims.push(self.num()?);
+                self.expect(']')?;
+            }
+            if self.is_punct(':') {
+                bail!("bit-field {name} is not supported");
+            }
+            let mut attrs = Attrs::default();
+            self.attrs(&mut attrs)?;
+
+            let (ty, spelled, size, align) = if ptr {
+                (CType::Ptr, format!("{spelled} *"), self.abi.ptr(), self.abi.ptr())
+            } else if spelled == "void" {
+                bail!("member {name} has type void");
+            } else {
+                (ty.clone(), spelled.clone(), size, align)
+            };
+            let n: usize = dims.iter().product();
+            let align = if attrs.packed { 1 } else { align };
+            members.push(Member { name, ty, spelled, dims, offset: 0, size: size * n, align, aligned: attrs.aligned });
+
+            match self.next()? {
+                Tok::Punct(',') => continue,
+                Tok::Punct(';') => return Ok(()),
+                t => bail!("expected ',' or ';', found {}", show(&t)),
+            }
+        }
+    }
+}
+
+fn show(t: &Tok) -> String {
+    match t {
+        Tok::Ident(w) => format!("'{w}'"),
+        Tok::Num(n) => n.to_string(),
+        Tok::Punct(c) => format!("'{c}'"),
+        Tok::Pack(_) => "#pragma pack".into(),
+    }
+}
+
+impl Header {
+    pub fn parse(src: &str, abi: Abi) -> Result<Header> {
+        let mut p = Parser {
+            toks: tokenize(src)?,
+            pos: 0,
+            abi,
+            pack: None,
+            pack_stack: Vec::new(),
+            layouts: Vec::new(),
+            by_name: HashMap::new(),
+            aliases: HashMap::new(),
+            anon: 0,
+        };
+        p.top()?;
+        Ok(Header { abi, layouts: p.layouts, aliases: p.aliases })
+    }
+
+    /// A struct by tag or typedef name.
+    pub fn get(&self, name: &str) -> Option<&Layout> {
+        let tag = self.aliases.get(name).map_or(name, String::as_str);
+        self.layouts.iter().find(|l| l.name == tag)
+    }
+
+    /// The named struct, or the last one defined.
+    pub fn pick(&self, name: Option<&str>) -> Result<&Layout> {
+        match name {
+            Some(n) => self.get(n).ok_or_else(|| anyhow!("no struct named {n} in header")),
+            None => self.layouts.last().ok_or_else(|| anyhow!("no struct definitions in header")),
+        }
+    }
+
+    /// `root` followed by every struct it contains, outermost first.
+    fn closure<'a>(&'a self, root: &'a Layout) -> Vec<&'a Layout> {
+        let mut out = vec![root];
+        let mut i = 0;
+        while i < out.len() {
+            for m in &out[i].members {
+                if let CType::Struct(n) = &m.ty {
+                    if let Some(l) = self.get(n) {
+                        if !out.iter().any(|o| o.name == l.name) {
+                            out.push(l);
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+        out
+    }
+
+    /// Table of offsets, sizes and padding for `root` and its nested structs.
+    pub fn render(&self, root: &Layout) -> String {
+        let abi = match self.abi {
+            Abi::Ilp32 => "ILP32",
+            Abi::Lp64 => "LP64",
+        };
+        let mut out = String::new();
+        for (i, l) in self.closure(root).into_iter().enumerate() {
+            if i > 0 { out.push('\n'); }
+            out.push_str(&format!("struct {} ({abi}): size {}, align {}\n", l.name, l.size, l.align));
+            out.push_str("  offset  size  type                  name\n");
+            let mut cursor = 0;
+            let pad = |out: &mut String, at: usize, n: usize| {
+                out.push_str(&format!("  0x{at:04x}  {n:4}  <padding>\n"));
+            };
+            for m in &l.members {
+                if m.offset > cursor {
+                    pad(&mut out, cursor, m.offset - cursor);
+                }
+                let dims: String = m.dims.iter().map(|d| format!("[{d}]")).collect();
+                out.push_str(&format!("  0x{:04x}  {:4}  {:<20}  {}{dims}\n", m.offset, m.size, m.spelled, m.name));
+                cursor = m.offset + m.size;
+            }
+            if l.size > cursor {
+                pad(&mut out, cursor, l.size - cursor);
+            }
+        }
+        out
+    }
+
+    /// A decode/encode schema equivalent to `root`, with padding as `pad` fields.
+    pub fn to_schema(&self, root: &Layout, endian: Endian) -> Result<Schema> {
+        let mut schema = Schema { endian: Some(endian), ..Schema::default() };
+        for l in self.closure(root) {
+            let mut fields = Vec::new();
+            let mut cursor = 0;
+            let pad = |n: usize| Field {
+                name: "_".into(),
+                kind: Kind::Pad,
+                endian: None,
+                count: Some(Count::Fixed(n)),
+                magic: None,
+                line: 0,
+            };
+            for m in &l.members {
+                if m.offset > cursor {
+                    fields.push(pad(m.offset - cursor));
+                }
+                let n: usize = m.dims.iter().product();
+                let kind = match &m.ty {
+                    CType::Char if !m.dims.is_empty() => Kind::Str,
+                    CType::Char => Kind::Int { width: Width::W8, signed: true },
+                    CType::Int { size, signed } => Kind::Int { width: Width::new(*size as u32 * 8)?, signed: *signed },
+                    CType::Ptr => Kind::Int { width: Width::new(self.abi.ptr() as u32 * 8)?, signed: false },
+                    CType::Float { size } => Kind::Float { width: Width::new(*size as u32 * 8)? },
+                    CType::Struct(s) => Kind::Struct(s.clone()),
+                };
+                let count = (!m.dims.is_empty()).then_some(Count::Fixed(n));
+                fields.push(Field { name: m.name.clone(), kind, endian: None, count, magic: None, line: 0 });
+                cursor = m.offset + m.size;
+            }
+            if l.size > cursor {
+                fields.push(pad(l.size - cursor));
+            }
+
+            if l.name == root.name {
+                schema.root = fields;
+            } else {
+                schema.structs.insert(l.name.clone(), fields);
+            }
+        }
+        Ok(schema)
+    }
+}
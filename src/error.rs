@@ -0,0 +1,147 @@
+use std::fmt;
+use std::num::ParseIntError;
+
+/// Where a token was found in textual input.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Loc {
+    pub index: usize,  // 0-based token number
+    pub offset: usize, // 0-based byte offset into the input
+    pub line: usize,   // 1-based; 0 when only the index is known
+    pub column: usize, // 1-based, in chars
+}
+
+impl Loc {
+    /// A position known only by token number, e.g. for values passed as integers.
+    pub fn index(index: usize) -> Self {
+        Loc { index, ..Loc::default() }
+    }
+
+    /// Position of `tokens[index]` when the tokens are read as one line
+    /// separated by single spaces, as for command-line values.
+    pub fn in_list<S: AsRef<str>>(tokens: &[S], index: usize) -> Self {
+        let before = &tokens[..index.min(tokens.len())];
+        let offset = before.iter().map(|t| t.as_ref().len() + 1).sum();
+        let column = before.iter().map(|t| t.as_ref().chars().count() + 1).sum::<usize>() + 1;
+        Loc { index, offset, line: 1, column }
+    }
+}
+
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "token {}", self.index);
+        }
+        write!(f, "token {} at line {}, column {} (offset {})", self.index, self.line, self.column, self.offset)
+    }
+}
+
+/// A byte from a bad-character set found in output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadHit {
+    pub offset: usize,
+    pub byte: u8,
+    /// The input value that produced the byte, when known.
+    pub origin: Option<String>,
+}
+
+/// Errors returned by the parsing and packing functions in `util`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PakxError {
+    /// A value could not be parsed as an integer.
+    BadInt { token: String, loc: Option<Loc>, source: ParseIntError },
+    /// A value is outside the range of the target width.
+    Overflow { value: i128, bits: u32, signed: bool, loc: Option<Loc> },
+    /// A negative value was given for an unsigned strict pack.
+    Negative { value: i128, loc: Option<Loc> },
+    /// A bitfield value is outside the range of its field.
+    FieldOverflow { value: i128, bits: u32, signed: bool, field: usize },
+    /// A hex token contains a non-hex character.
+    BadDigit { token: String, loc: Loc },
+    /// A hex token has an odd number of nibbles.
+    OddNibbles { token: String, loc: Loc },
+    /// Fewer bytes were available than a value needs.
+    Truncated { needed: usize, got: usize, offset: usize },
+    /// A width other than 8, 16, 32, 64 or 128 bits.
+    UnsupportedWidth { bits: u32 },
+    /// A width that is not a number at all.
+    BadWidth { token: String },
+    /// Output contains bytes from the bad-character set.
+    BadChars { hits: Vec<BadHit> },
+}
+
+impl PakxError {
+    /// Attach the position of the value that caused the error, if it has none yet.
+    pub fn at(mut self, at: Loc) -> Self {
+        match &mut self {
+            PakxError::BadInt { loc, .. }
+            | PakxError::Overflow { loc, .. }
+            | PakxError::Negative { loc, .. } => {
+                loc.get_or_insert(at);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Like `at`, when only the token number is known.
+    pub fn at_index(self, i: usize) -> Self {
+        self.at(Loc::index(i))
+    }
+}
+
+fn write_loc(f: &mut fmt::Formatter<'_>, loc: Option<Loc>) -> fmt::Result {
+    match loc {
+        Some(l) => write!(f, "{l}: "),
+        None => Ok(()),
+    }
+}
+
+fn kind(signed: bool) -> &'static str {
+    if signed { "signed" } else { "unsigned" }
+}
+
+impl fmt::Display for PakxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PakxError::BadInt { token, loc, source } => {
+                write_loc(f, *loc)?;
+                write!(f, "invalid integer {token:?}: {source}")
+            }
+            PakxError::Overflow { value, bits, signed, loc } => {
+                write_loc(f, *loc)?;
+                write!(f, "value {value} does not fit in {} {bits}-bit", kind(*signed))
+            }
+            PakxError::FieldOverflow { value, bits, signed, field } => {
+                write!(f, "field {field}: value {value} does not fit in {} {bits}-bit", kind(*signed))
+            }
+            PakxError::Negative { value, loc } => {
+                write_loc(f, *loc)?;
+                write!(f, "negative value {value} not allowed for unsigned (use --signed or remove --strict)")
+            }
+            PakxError::BadDigit { token, loc } => write!(f, "non-hex digit in {loc}: {token}"),
+            PakxError::OddNibbles { token, loc } => write!(f, "odd number of hex nibbles in {loc}: {token}"),
+            PakxError::Truncated { needed, got, offset } => {
+                write!(f, "truncated input at offset {offset}: need {needed} bytes, got {got}")
+            }
+            PakxError::BadChars { hits } => {
+                write!(f, "output contains {} bad byte(s):", hits.len())?;
+                for h in hits {
+                    write!(f, "\n  offset 0x{:04x}: 0x{:02x}", h.offset, h.byte)?;
+                    if let Some(o) = &h.origin {
+                        write!(f, " from {o}")?;
+                    }
+                }
+                Ok(())
+            }
+            PakxError::BadWidth { token } => {
+                write!(f, "invalid width {token:?}: must be one of 8, 16, 32, 64, 128")
+            }
+            PakxError::UnsupportedWidth { bits } => {
+                write!(f, "unsupported width {bits}: must be one of 8, 16, 32, 64, 128")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PakxError {}
@@ -1,7 +1,37 @@
+pub mod api;
+pub mod checksum;
+pub mod cli;
+pub mod cstruct;
+pub mod error;
+pub mod json;
+pub mod schema;
+pub mod time;
 pub mod util;
 pub mod cmd {
     pub mod pack;
     pub mod unpack;
     pub mod bswap;
     pub mod bytes;
+    pub mod addr;
+    pub mod bits;
+    pub mod bitops;
+    pub mod byteops;
+    pub mod conv;
+    pub mod cstruct;
+    pub mod decode;
+    pub mod diff;
+    pub mod encode;
+    pub mod find;
+    pub mod flat;
+    pub mod info;
+    pub mod pad;
+    pub mod patch;
+    pub mod slice;
+    pub mod sum;
+    pub mod time;
+    pub mod uuid;
+    pub mod fmtstr;
 }
+
+pub use api::{Formatter, Packer, Unpacker};
+pub use error::PakxError;
@@ -1,8 +1,36 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand, Args, ValueEnum};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, Args};
 
 use pakx::cmd::{pack::run_pack, unpack::run_unpack, bswap::run_bswap, bytes::run_bytes};
-use pakx::util::{Endian, OutFmt};
+use pakx::cmd::byteops::{run_byteop, run_xor_brute, ByteOp, ByteOpArgs, Key, XorArgs};
+use pakx::cmd::addr::{run_addr_pack, run_addr_unpack, AddrCmd};
+use pakx::cmd::bitops::{run_bitop, run_bitop_value, BitOp, BitOpArgs};
+use pakx::cmd::bits::{run_bits_pack, run_bits_unpack, BitOrder, BitsCmd};
+use pakx::cmd::conv::{run_conv, ConvArgs};
+use pakx::cmd::decode::{run_decode, DecodeArgs};
+use pakx::cmd::encode::{run_encode, EncodeArgs};
+use pakx::cmd::cstruct::{run_cstruct_decode, run_cstruct_show, CstructArgs};
+use pakx::cstruct::Header;
+use pakx::cmd::diff::{run_diff, DiffArgs};
+use pakx::cmd::find::{run_find, FindArgs};
+use pakx::cmd::info::{run_info, InfoArgs};
+use pakx::cmd::pad::{run_pad, PadArgs};
+use pakx::cmd::patch::{run_patch, PatchArgs};
+use pakx::cmd::slice::{run_slice, SliceArgs};
+use pakx::cmd::sum::{run_sum, SumArgs};
+use pakx::cmd::time::{run_time_pack, run_time_unpack, TimeCmd};
+use pakx::cmd::uuid::{run_uuid_pack, run_uuid_unpack, UuidCmd};
+use pakx::cmd::flat::{run_flat, FlatArgs};
+use pakx::cmd::fmtstr::{run_fmtstr, FmtstrArgs};
+use pakx::schema::Schema;
+use pakx::cli::{endian_from, infmt_of, outfmt_of, parse_byte, parse_size, parse_width};
+use pakx::cli::{BadcharArgs, ByteIoArgs, InFmtArg, OutFmtArg};
+use pakx::util::{Endian, Width};
+use pakx::api::BadChars;
+use pakx::{Formatter, Packer, Unpacker};
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(
@@ -29,6 +57,33 @@ enum Cmd {
     Unpack(GeneralUnpack),
     Bswap(BswapArgs),
     Bytes(BytesArgs),
+    #[command(subcommand)]
+    Bits(BitsCmd),
+    Decode(DecodeArgs),
+    Encode(EncodeArgs),
+    Cstruct(CstructArgs),
+    Flat(FlatArgs),
+    Fmtstr(FmtstrArgs),
+    Sum(SumArgs),
+    Patch(PatchArgs),
+    Find(FindArgs),
+    Diff(DiffArgs),
+    Slice(SliceArgs),
+    Pad(PadArgs),
+    Conv(ConvArgs),
+    Info(InfoArgs),
+    #[command(subcommand)]
+    Addr(AddrCmd),
+    #[command(subcommand)]
+    Time(TimeCmd),
+    #[command(subcommand)]
+    Uuid(UuidCmd),
+    Xor(XorArgs),
+    And(ByteOpArgs),
+    Or(ByteOpArgs),
+    Not(ByteIoArgs),
+    Add(ByteOpArgs),
+    Sub(ByteOpArgs),
     P8(PackSugar),
     P16(PackSugar),
     P32(PackSugar),
@@ -44,55 +99,31 @@ enum Cmd {
 #[derive(Args)]
 struct GeneralPack {
     #[arg(long, value_parser = parse_width)]
-    width: u32,
-    #[arg(long, conflicts_with = "le")]
-    be: bool,
-    #[arg(long, conflicts_with = "be")]
-    le: bool,
-    #[arg(long)]
-    signed: bool,
-    #[arg(long, value_enum, default_value_t = OutFmtArg::Raw)]
-    out: OutFmtArg,
-    #[arg(long, default_value = " ")]
-    sep: String,
-    #[arg(long)]
-    uppercase: bool,
-    #[arg(long, conflicts_with = "trunc")]
-    strict: bool,
-    #[arg(long, hide = true)]
-    trunc: bool,
-    #[arg(long)]
-    repeat: Option<usize>,
-    #[arg(allow_negative_numbers = true)]
-    values: Vec<String>,
+    width: Width,
+    #[command(flatten)]
+    opts: PackSugar,
 }
 
 #[derive(Args)]
 struct GeneralUnpack {
     #[arg(long, value_parser = parse_width)]
-    width: u32,
-    #[arg(long, value_enum, default_value_t = InFmtArg::Raw)]
-    r#in: InFmtArg,
-    #[arg(long, conflicts_with = "le")]
-    be: bool,
-    #[arg(long, conflicts_with = "be")]
-    le: bool,
-    #[arg(long)]
-    signed: bool,
-    #[arg(long)]
-    count: Option<usize>,
+    width: Width,
+    #[command(flatten)]
+    opts: UnpackSugar,
 }
 
 #[derive(Args)]
 struct BswapArgs {
     #[arg(long, value_parser = parse_width)]
-    width: u32,
+    width: Width,
     #[arg(long, value_enum, default_value_t = OutFmtArg::Hex)]
     out: OutFmtArg,
     #[arg(long, default_value = " ")]
     sep: String,
     #[arg(long)]
     uppercase: bool,
+    #[command(flatten)]
+    bad: BadcharArgs,
     value: String,
 }
 
@@ -106,6 +137,8 @@ struct BytesArgs {
     sep: String,
     #[arg(long)]
     uppercase: bool,
+    #[command(flatten)]
+    bad: BadcharArgs,
 }
 
 #[derive(Args)]
@@ -122,12 +155,32 @@ struct PackSugar {
     sep: String,
     #[arg(long)]
     uppercase: bool,
+    #[command(flatten)]
+    bad: BadcharArgs,
     #[arg(long, conflicts_with = "trunc")]
     strict: bool,
     #[arg(long, hide = true)]
     trunc: bool,
     #[arg(long)]
     repeat: Option<usize>,
+    /// Align each value to a multiple of its own size, like Python struct's `@`.
+    #[arg(long)]
+    natural: bool,
+    /// Pad the output to a multiple of N bytes.
+    #[arg(long, value_parser = parse_size)]
+    align: Option<usize>,
+    #[arg(long, value_parser = parse_byte, default_value = "0")]
+    pad_byte: u8,
+    /// Repeat the packed output cyclically to exactly LEN bytes.
+    #[arg(long, value_parser = parse_size)]
+    fill_to: Option<usize>,
+    /// Cut the output to at most LEN bytes.
+    #[arg(long, value_parser = parse_size)]
+    max_len: Option<usize>,
+    /// Print the offset of every value and padding run to stderr.
+    #[arg(long)]
+    explain: bool,
+    /// Values, optionally typed as TYPE:VALUE (e.g. u8:1 u32:0x10).
     #[arg(allow_negative_numbers = true)]
     values: Vec<String>,
 }
@@ -146,67 +199,209 @@ struct UnpackSugar {
     count: Option<usize>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
-enum OutFmtArg { Raw, Hex, C, Py }
-#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
-enum InFmtArg { Raw, Hex }
+fn badchars_of(bad: &BadcharArgs) -> Option<BadChars> {
+    let warn = |e: &pakx::PakxError| eprintln!("warning: {e}");
+    bad.badchars.clone().map(|b| b.warn(bad.badchars_warn).on_warning(warn))
+}
 
-fn endian_from(be: bool, _le: bool) -> Endian {
-    if be { Endian::Big } else { Endian::Little }
+fn formatter(out: OutFmtArg, sep: &str, uppercase: bool, bad: &BadcharArgs) -> Formatter {
+    Formatter::new(outfmt_of(out)).sep(sep).uppercase(uppercase).badchars(badchars_of(bad))
 }
 
+fn pack(width: Width, a: &PackSugar) -> Result<()> {
+    let packer = Packer::new(width)
+        .endian(endian_from(a.be, a.le))
+        .signed(a.signed)
+        .strict(a.strict)
+        .repeat(a.repeat)
+        .natural(a.natural)
+        .align(a.align)
+        .pad_byte(a.pad_byte)
+        .fill_to(a.fill_to)
+        .max_len(a.max_len);
+    let mut stderr = io::stderr();
+    let explain = a.explain.then_some(&mut stderr as &mut dyn io::Write);
+    run_pack(&packer, &a.values, &formatter(a.out, &a.sep, a.uppercase, &a.bad), &mut io::stdout().lock(), explain)
+}
+
+fn unpack(width: Width, a: &UnpackSugar) -> Result<()> {
+    let unpacker = Unpacker::new(width)
+        .endian(endian_from(a.be, a.le))
+        .signed(a.signed)
+        .count(a.count)
+        .input(infmt_of(a.r#in));
+    run_unpack(&unpacker, io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Like `cmp`: exit 0 when identical, 1 when different, 2 on trouble.
+fn diff(a: &DiffArgs) -> ! {
+    let run = || -> Result<bool> {
+        let read = |p: &PathBuf| std::fs::read(p).with_context(|| format!("cannot read {}", p.display()));
+        let (x, y) = (read(&a.a)?, read(&a.b)?);
+        let names = (a.a.display().to_string(), a.b.display().to_string());
+        run_diff((&names.0, &names.1), &x, &y, &a.opts(), &mut io::stdout().lock())
+    };
+    match run() {
+        Ok(differ) => std::process::exit(differ as i32),
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            std::process::exit(2)
+        }
+    }
+}
 
-fn outfmt_of(arg: OutFmtArg) -> OutFmt {
-    match arg {
-        OutFmtArg::Raw => OutFmt::Raw,
-        OutFmtArg::Hex => OutFmt::Hex,
-        OutFmtArg::C   => OutFmt::C,
-        OutFmtArg::Py  => OutFmt::Py,
+fn bitop(op: BitOp, a: &BitOpArgs) -> Result<()> {
+    let fmt = formatter(a.io.out, &a.io.sep, a.io.uppercase, &a.io.bad);
+    let (endian, out) = (endian_from(a.be, a.le), &mut io::stdout().lock());
+    match &a.value {
+        Some(v) => run_bitop_value(op, a.width, endian, v, &fmt, out),
+        None => run_bitop(op, a.width, endian, infmt_of(a.io.r#in), &fmt, io::stdin().lock(), out),
     }
 }
 
+fn byteop(op: ByteOp, key: &Key, a: &ByteIoArgs) -> Result<()> {
+    let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+    run_byteop(op, key, infmt_of(a.r#in), &fmt, io::stdin().lock(), &mut io::stdout().lock())
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.cmd {
-        Cmd::Pack(a) => {
-            let endian = endian_from(a.be, a.le);
-            run_pack(a.width, &a.values, endian, a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat)
+        Cmd::Pack(a) => pack(a.width, &a.opts),
+        Cmd::Unpack(a) => unpack(a.width, &a.opts),
+        Cmd::Bswap(a) => {
+            run_bswap(a.width, &a.value, &formatter(a.out, &a.sep, a.uppercase, &a.bad), &mut io::stdout().lock())
+        }
+        Cmd::Bytes(a) => {
+            run_bytes(infmt_of(a.r#in), &formatter(a.out, &a.sep, a.uppercase, &a.bad), io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Bits(BitsCmd::Pack(a)) => {
+            let packer = Packer::new(a.width).endian(endian_from(a.be, a.le));
+            run_bits_pack(&packer, &a.fields, BitOrder::from_msb0(a.msb0), a.signed, &formatter(a.out, &a.sep, a.uppercase, &a.bad), &mut io::stdout().lock())
+        }
+        Cmd::Bits(BitsCmd::Unpack(a)) => {
+            let words = Unpacker::new(a.width).endian(endian_from(a.be, a.le)).input(infmt_of(a.r#in));
+            run_bits_unpack(&words, &a.fields, BitOrder::from_msb0(a.msb0), a.signed, io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Bits(BitsCmd::Rev(a)) => bitop(BitOp::Reverse, &a),
+        Cmd::Bits(BitsCmd::Nibswap(a)) => bitop(BitOp::SwapNibbles, &a),
+        Cmd::Bits(BitsCmd::Rol(a)) => bitop(BitOp::Rol(a.by), &a.op),
+        Cmd::Bits(BitsCmd::Ror(a)) => bitop(BitOp::Ror(a.by), &a.op),
+        Cmd::Decode(a) => {
+            let schema = Schema::parse(&std::fs::read_to_string(&a.schema)?)?;
+            let out = &mut io::stdout().lock();
+            match &a.file {
+                Some(p) => run_decode(&schema, infmt_of(a.r#in), File::open(p)?, out),
+                None => run_decode(&schema, infmt_of(a.r#in), io::stdin().lock(), out),
+            }
         }
-        Cmd::Unpack(a) => {
+        Cmd::Encode(a) => {
+            let schema = Schema::parse(&std::fs::read_to_string(&a.schema)?)?;
+            let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+            let out = &mut io::stdout().lock();
+            match &a.file {
+                Some(p) => run_encode(&schema, &fmt, File::open(p)?, out),
+                None => run_encode(&schema, &fmt, io::stdin().lock(), out),
+            }
+        }
+        Cmd::Cstruct(a) => {
+            let header = Header::parse(&std::fs::read_to_string(&a.header)?, a.abi)?;
+            let out = &mut io::stdout().lock();
             let endian = endian_from(a.be, a.le);
-            run_unpack(a.width, matches!(a.r#in, InFmtArg::Hex), endian, a.signed, a.count)
+            match (&a.data, a.show) {
+                (_, true) => run_cstruct_show(&header, a.name.as_deref(), out),
+                (Some(p), false) => run_cstruct_decode(&header, a.name.as_deref(), endian, infmt_of(a.r#in), File::open(p)?, out),
+                (None, false) => run_cstruct_decode(&header, a.name.as_deref(), endian, infmt_of(a.r#in), io::stdin().lock(), out),
+            }
         }
-        Cmd::Bswap(a) => {
-            run_bswap(a.width, &a.value, outfmt_of(a.out), &a.sep, a.uppercase)
+        Cmd::Flat(a) => {
+            let mut stderr = io::stderr();
+            let map = (!a.quiet).then_some(&mut stderr as &mut dyn io::Write);
+            run_flat(&a.items, &a.opts(), &formatter(a.out, &a.sep, a.uppercase, &a.bad), &mut io::stdout().lock(), map)
         }
-        Cmd::Bytes(a) => {
-            run_bytes(matches!(a.r#in, InFmtArg::Hex), outfmt_of(a.out), &a.sep, a.uppercase)
+        Cmd::Fmtstr(a) => {
+            let mut stderr = io::stderr();
+            let explain = a.explain.then_some(&mut stderr as &mut dyn io::Write);
+            run_fmtstr(&a.writes, &a.opts(), &formatter(a.out, &a.sep, a.uppercase, &a.bad), &mut io::stdout().lock(), explain)
+        }
+        Cmd::Sum(a) => {
+            let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+            run_sum(&a.algo()?, endian_from(a.be, a.le), infmt_of(a.r#in), &fmt, io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Patch(a) => {
+            let expect = a.expect.as_deref().map(pakx::util::parse_hex_str).transpose()?;
+            run_patch(&a.file, a.at, &a.new_bytes()?, expect.as_deref(), a.output.as_deref(), badchars_of(&a.bad).as_ref())
+        }
+        Cmd::Find(a) => {
+            let needles = a.needles()?;
+            let out = &mut io::stdout().lock();
+            match &a.file {
+                Some(p) => run_find(&needles, a.aligned, a.max, File::open(p)?, out),
+                None => run_find(&needles, a.aligned, a.max, io::stdin().lock(), out),
+            }
+        }
+        Cmd::Diff(a) => diff(&a),
+        Cmd::Slice(a) => {
+            let span = a.span();
+            let fmt = formatter(a.io.out, &a.io.sep, a.io.uppercase, &a.io.bad);
+            let out = &mut io::stdout().lock();
+            match &a.file {
+                Some(p) => run_slice(a.offset, span, infmt_of(a.io.r#in), &fmt, File::open(p)?, out),
+                None => run_slice(a.offset, span, infmt_of(a.io.r#in), &fmt, io::stdin().lock(), out),
+            }
+        }
+        Cmd::Pad(a) => {
+            let fmt = formatter(a.io.out, &a.io.sep, a.io.uppercase, &a.io.bad);
+            run_pad(&a.opts(), infmt_of(a.io.r#in), &fmt, io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Conv(a) => run_conv(&a.value, a.from, a.to, endian_from(a.be, a.le), &mut io::stdout().lock()),
+        Cmd::Info(a) => run_info(&a.value, &mut io::stdout().lock()),
+        Cmd::Addr(AddrCmd::Pack(a)) => {
+            let endian = if a.le { Endian::Little } else { Endian::Big };
+            let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+            run_addr_pack(&a.values, endian, &fmt, &mut io::stdout().lock())
         }
+        Cmd::Addr(AddrCmd::Unpack(a)) => {
+            let endian = if a.le { Endian::Little } else { Endian::Big };
+            run_addr_unpack(&a.kinds, endian, infmt_of(a.r#in), io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Time(TimeCmd::Pack(a)) => {
+            let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+            run_time_pack(&a.values, a.format, endian_from(a.be, a.le), &fmt, &mut io::stdout().lock())
+        }
+        Cmd::Time(TimeCmd::Unpack(a)) => {
+            run_time_unpack(a.format, endian_from(a.be, a.le), infmt_of(a.r#in), io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Uuid(UuidCmd::Pack(a)) => {
+            let mut stderr = io::stderr();
+            let explain = a.explain.then_some(&mut stderr as &mut dyn io::Write);
+            let fmt = formatter(a.out, &a.sep, a.uppercase, &a.bad);
+            run_uuid_pack(&a.values, a.layout, &fmt, &mut io::stdout().lock(), explain)
+        }
+        Cmd::Uuid(UuidCmd::Unpack(a)) => {
+            run_uuid_unpack(a.layout, infmt_of(a.r#in), io::stdin().lock(), &mut io::stdout().lock())
+        }
+        Cmd::Xor(a) if a.brute => run_xor_brute(infmt_of(a.op.io.r#in), a.top, io::stdin().lock(), &mut io::stdout().lock()),
+        Cmd::Xor(a) => byteop(ByteOp::Xor, &a.op.key.key()?, &a.op.io),
+        Cmd::And(a) => byteop(ByteOp::And, &a.key.key()?, &a.io),
+        Cmd::Or(a)  => byteop(ByteOp::Or,  &a.key.key()?, &a.io),
+        Cmd::Not(a) => byteop(ByteOp::Not, &Key::default(), &a),
+        Cmd::Add(a) => byteop(ByteOp::Add, &a.key.key()?, &a.io),
+        Cmd::Sub(a) => byteop(ByteOp::Sub, &a.key.key()?, &a.io),
 
         // Sugar: p*
-        Cmd::P8(a)   => run_pack(8,   &a.values, endian_from(a.be, a.le), a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat),
-        Cmd::P16(a)  => run_pack(16,  &a.values, endian_from(a.be, a.le), a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat),
-        Cmd::P32(a)  => run_pack(32,  &a.values, endian_from(a.be, a.le), a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat),
-        Cmd::P64(a)  => run_pack(64,  &a.values, endian_from(a.be, a.le), a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat),
-        Cmd::P128(a) => run_pack(128, &a.values, endian_from(a.be, a.le), a.signed, outfmt_of(a.out), &a.sep, a.uppercase, a.strict, a.repeat),
+        Cmd::P8(a)   => pack(Width::W8,   &a),
+        Cmd::P16(a)  => pack(Width::W16,  &a),
+        Cmd::P32(a)  => pack(Width::W32,  &a),
+        Cmd::P64(a)  => pack(Width::W64,  &a),
+        Cmd::P128(a) => pack(Width::W128, &a),
 
         // Sugar: u*
-        Cmd::U8(a)   => run_unpack(8,   matches!(a.r#in, InFmtArg::Hex), endian_from(a.be, a.le), a.signed, a.count),
-        Cmd::U16(a)  => run_unpack(16,  matches!(a.r#in, InFmtArg::Hex), endian_from(a.be, a.le), a.signed, a.count),
-        Cmd::U32(a)  => run_unpack(32,  matches!(a.r#in, InFmtArg::Hex), endian_from(a.be, a.le), a.signed, a.count),
-        Cmd::U64(a)  => run_unpack(64,  matches!(a.r#in, InFmtArg::Hex), endian_from(a.be, a.le), a.signed, a.count),
-        Cmd::U128(a) => run_unpack(128, matches!(a.r#in, InFmtArg::Hex), endian_from(a.be, a.le), a.signed, a.count),
-    }
-}
-
-fn parse_width(s: &str) -> Result<u32, String> {
-    match s {
-        "8" => Ok(8),
-        "16" => Ok(16),
-        "32" => Ok(32),
-        "64" => Ok(64),
-        "128" => Ok(128),
-        _ => Err("width must be one of: 8, 16, 32, 64, 128".into()),
+        Cmd::U8(a)   => unpack(Width::W8,   &a),
+        Cmd::U16(a)  => unpack(Width::W16,  &a),
+        Cmd::U32(a)  => unpack(Width::W32,  &a),
+        Cmd::U64(a)  => unpack(Width::W64,  &a),
+        Cmd::U128(a) => unpack(Width::W128, &a),
     }
 }
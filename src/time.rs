@@ -0,0 +1,233 @@
+//! UTC timestamps and the binary encodings used by `pakx time`.
+//!
+//! Civil dates are converted with Howard Hinnant's `days_from_civil` and
+//! `civil_from_days`, so no timezone database is involved: an input offset
+//! such as `+02:00` is subtracted arithmetically and output is always UTC.
+
+use anyhow::{anyhow, bail, Result};
+
+/// Seconds and nanoseconds since 1970-01-01T00:00:00Z.
+#[derive(Copy, Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Timestamp {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12; // March is 0
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of `days_from_civil`: (year, month, day).
+pub fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn num(s: &str) -> Option<i64> {
+    (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())).then(|| s.parse().ok()).flatten()
+}
+
+impl Timestamp {
+    /// Parse `YYYY-MM-DD[THH:MM[:SS[.frac]]][Z|±HH:MM]`; a space may replace
+    /// the `T`, and a missing offset means UTC.
+    pub fn parse(s: &str) -> Result<Self> {
+        let bad = || anyhow!("invalid timestamp {s:?}: expected YYYY-MM-DDTHH:MM:SS[.frac][Z|+HH:MM]");
+        let (date, rest) = s.split_at(s.find(['T', 't', ' ']).unwrap_or(s.len()));
+        let rest = rest.get(1..).unwrap_or("");
+
+        let mut d = date.split('-');
+        let (y, m, day) = match (d.next(), d.next(), d.next(), d.next()) {
+            (Some(y), Some(m), Some(day), None) if y.len() == 4 && m.len() == 2 && day.len() == 2 => {
+                (num(y).ok_or_else(bad)?, num(m).ok_or_else(bad)? as u32, num(day).ok_or_else(bad)? as u32)
+            }
+            _ => return Err(bad()),
+        };
+        if !(1..=12).contains(&m) || day == 0 || day > days_in_month(y, m) {
+            bail!("invalid timestamp {s:?}: no such date");
+        }
+
+        // Split off the zone designator, then the fraction.
+        let (clock, offset) = match rest.find(['Z', 'z', '+', '-']) {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let (hms, frac) = match clock.split_once('.') {
+            Some((_, "")) => return Err(bad()),
+            Some(parts) => parts,
+            None => (clock, ""),
+        };
+        let mut t = if hms.is_empty() { Vec::new() } else { hms.split(':').collect::<Vec<_>>() };
+        if t.len() == 2 {
+            t.push("00");
+        }
+        let (h, min, sec) = match t[..] {
+            [] => (0, 0, 0),
+            [h, mi, se] if h.len() == 2 && mi.len() == 2 && se.len() == 2 => {
+                (num(h).ok_or_else(bad)?, num(mi).ok_or_else(bad)?, num(se).ok_or_else(bad)?)
+            }
+            _ => return Err(bad()),
+        };
+        if h > 23 || min > 59 || sec > 59 {
+            bail!("invalid timestamp {s:?}: time of day out of range");
+        }
+        let nanos = if frac.is_empty() {
+            0
+        } else if frac.len() <= 9 {
+            (num(frac).ok_or_else(bad)? * 10i64.pow(9 - frac.len() as u32)) as u32
+        } else {
+            return Err(bad());
+        };
+
+        let offset_secs = match offset {
+            "" | "Z" | "z" => 0,
+            o => {
+                let sign = if o.starts_with('-') { -1 } else { 1 };
+                let (oh, om) = match o[1..].split_once(':') {
+                    Some(parts) => parts,
+                    None => (o.get(1..3).ok_or_else(bad)?, o.get(3..).ok_or_else(bad)?),
+                };
+                if oh.len() != 2 || om.len() != 2 {
+                    return Err(bad());
+                }
+                let (oh, om) = (num(oh).ok_or_else(bad)?, num(om).ok_or_else(bad)?);
+                if oh > 23 || om > 59 {
+                    bail!("invalid timestamp {s:?}: UTC offset out of range");
+                }
+                sign * (oh * 3600 + om * 60)
+            }
+        };
+
+        let secs = days_from_civil(y, m, day) * 86_400 + h * 3600 + min * 60 + sec - offset_secs;
+        Ok(Self { secs, nanos })
+    }
+
+    /// ISO-8601 in UTC; the fraction is shown only when non-zero.
+    pub fn to_iso(&self) -> String {
+        let (y, m, d) = civil_from_days(self.secs.div_euclid(86_400));
+        let t = self.secs.rem_euclid(86_400);
+        let mut s = format!("{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}", t / 3600, t / 60 % 60, t % 60);
+        if self.nanos != 0 {
+            s.push_str(format!(".{:09}", self.nanos).trim_end_matches('0'));
+        }
+        s.push('Z');
+        s
+    }
+}
+
+/// Seconds from 1601-01-01 (FILETIME) and 1904-01-01 (HFS) to the Unix epoch.
+const FILETIME_EPOCH: i128 = 11_644_473_600;
+const HFS_EPOCH: i128 = 2_082_844_800;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum TimeFormat {
+    /// Signed 32-bit seconds since 1970.
+    Unix32,
+    /// Signed 64-bit seconds since 1970.
+    Unix64,
+    /// Signed 64-bit milliseconds since 1970.
+    UnixMs,
+    /// Windows FILETIME: unsigned 64-bit 100ns ticks since 1601.
+    Filetime,
+    /// MS-DOS date and time as one 32-bit value, date in the high half; 2-second resolution.
+    #[value(name = "dosdate")]
+    DosDate,
+    /// Unsigned 32-bit seconds since 1904, as in HFS+.
+    Hfs,
+}
+
+impl TimeFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeFormat::Unix32 => "unix32",
+            TimeFormat::Unix64 => "unix64",
+            TimeFormat::UnixMs => "unix-ms",
+            TimeFormat::Filetime => "filetime",
+            TimeFormat::DosDate => "dosdate",
+            TimeFormat::Hfs => "hfs",
+        }
+    }
+
+    /// Width in bits and signedness of the encoded value.
+    pub fn int_type(self) -> (u32, bool) {
+        match self {
+            TimeFormat::Unix32 => (32, true),
+            TimeFormat::Unix64 | TimeFormat::UnixMs => (64, true),
+            TimeFormat::Filetime => (64, false),
+            TimeFormat::DosDate | TimeFormat::Hfs => (32, false),
+        }
+    }
+
+    /// The integer this format stores for `ts`, rounded down to its resolution.
+    pub fn encode(self, ts: Timestamp) -> Result<i128> {
+        let secs = ts.secs as i128;
+        let v = match self {
+            TimeFormat::Unix32 | TimeFormat::Unix64 => secs,
+            TimeFormat::UnixMs => secs * 1000 + (ts.nanos / 1_000_000) as i128,
+            TimeFormat::Filetime => (secs + FILETIME_EPOCH) * 10_000_000 + (ts.nanos / 100) as i128,
+            TimeFormat::Hfs => secs + HFS_EPOCH,
+            TimeFormat::DosDate => {
+                let (y, m, d) = civil_from_days(ts.secs.div_euclid(86_400));
+                if !(1980..=2107).contains(&y) {
+                    bail!("{} is outside the dosdate range (1980 to 2107)", ts.to_iso());
+                }
+                let t = ts.secs.rem_euclid(86_400);
+                let date = ((y - 1980) << 9) as i128 | ((m << 5) | d) as i128;
+                let time = ((t / 3600) << 11) | ((t / 60 % 60) << 5) | ((t % 60) / 2);
+                return Ok((date << 16) | time as i128);
+            }
+        };
+        let (bits, signed) = self.int_type();
+        let (lo, hi) = if signed { (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1) } else { (0, (1i128 << bits) - 1) };
+        if !(lo..=hi).contains(&v) {
+            bail!("{} is outside the range of {}", ts.to_iso(), self.name());
+        }
+        Ok(v)
+    }
+
+    pub fn decode(self, v: i128) -> Result<Timestamp> {
+        let split = |v: i128, per_sec: i128| Timestamp {
+            secs: v.div_euclid(per_sec) as i64,
+            nanos: (v.rem_euclid(per_sec) * (1_000_000_000 / per_sec)) as u32,
+        };
+        Ok(match self {
+            TimeFormat::Unix32 | TimeFormat::Unix64 => Timestamp { secs: v as i64, nanos: 0 },
+            TimeFormat::UnixMs => split(v, 1000),
+            TimeFormat::Filetime => split(v - FILETIME_EPOCH * 10_000_000, 10_000_000),
+            TimeFormat::Hfs => Timestamp { secs: (v - HFS_EPOCH) as i64, nanos: 0 },
+            TimeFormat::DosDate => {
+                let (date, time) = ((v >> 16) as u32, (v & 0xffff) as u32);
+                let (y, m, d) = (1980 + (date >> 9) as i64, (date >> 5) & 0xf, date & 0x1f);
+                let (h, min, sec) = (time >> 11, (time >> 5) & 0x3f, (time & 0x1f) * 2);
+                if !(1..=12).contains(&m) || d == 0 || d > days_in_month(y, m) || h > 23 || min > 59 || sec > 59 {
+                    bail!("invalid DOS date/time 0x{v:08x}");
+                }
+                let secs = days_from_civil(y, m, d) * 86_400 + (h * 3600 + min * 60 + sec) as i64;
+                Timestamp { secs, nanos: 0 }
+            }
+        })
+    }
+}
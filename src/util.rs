@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use crate::error::{Loc, PakxError};
 use std::io::{self, Read, Write};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -7,58 +8,188 @@ pub enum Endian {
     Big,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum OutFmt {
+    #[default]
     Raw,
     Hex,
     C,   // \xHH\xHH...
     Py,  // b"\xHH..."
 }
 
+/// Largest buffer a command builds from sizes given on the command line, so
+/// a typo like `@0xffffffffffff` fails cleanly instead of exhausting memory.
+pub const MAX_OUTPUT: usize = 1 << 28;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum InFmt {
     Raw,
     Hex,
 }
 
-pub fn width_bytes(width_bits: u32) -> usize {
-    match width_bits {
-        8 => 1,
-        16 => 2,
-        32 => 4,
-        64 => 8,
-        128 => 16,
-        _ => panic!("unsupported width: {width_bits}"),
+/// A validated integer width: 8, 16, 32, 64 or 128 bits.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Width(u32);
+
+impl Width {
+    pub const W8: Width = Width(8);
+    pub const W16: Width = Width(16);
+    pub const W32: Width = Width(32);
+    pub const W64: Width = Width(64);
+    pub const W128: Width = Width(128);
+
+    pub fn new(bits: u32) -> Result<Self, PakxError> {
+        match bits {
+            8 | 16 | 32 | 64 | 128 => Ok(Width(bits)),
+            _ => Err(PakxError::UnsupportedWidth { bits }),
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn bytes(self) -> usize {
+        self.0 as usize / 8
+    }
+}
+
+impl TryFrom<u32> for Width {
+    type Error = PakxError;
+
+    fn try_from(bits: u32) -> Result<Self, PakxError> {
+        Width::new(bits)
+    }
+}
+
+impl std::str::FromStr for Width {
+    type Err = PakxError;
+
+    fn from_str(s: &str) -> Result<Self, PakxError> {
+        let t = s.trim();
+        let bits = t.parse().map_err(|_| PakxError::BadWidth { token: t.to_string() })?;
+        Width::new(bits)
     }
 }
 
-pub fn parse_int(s: &str) -> Result<i128> {
+impl std::fmt::Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Bytes in a word of `width_bits`; fails like `Width::new` on other widths.
+pub fn width_bytes(width_bits: u32) -> Result<usize, PakxError> {
+    Ok(Width::new(width_bits)?.bytes())
+}
+
+pub fn parse_int(s: &str) -> Result<i128, PakxError> {
     let t = s.trim();
-    if let Some(h) = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")) {
-        Ok(i128::from_str_radix(h, 16)?)
+    let r = if let Some(h) = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")) {
+        i128::from_str_radix(h, 16)
+    } else if let Some(b) = t.strip_prefix("0b").or_else(|| t.strip_prefix("0B")) {
+        i128::from_str_radix(b, 2)
     } else {
-        Ok(t.parse::<i128>()?)
+        t.parse::<i128>()
+    };
+    r.map_err(|source| PakxError::BadInt { token: t.to_string(), loc: None, source })
+}
+
+/// Evaluate arithmetic over `parse_int` literals, e.g. `0x200+0x40` or
+/// `-4*0x10`: `+`, `-`, `*` and unary minus with the usual precedence.
+/// A doubled unary minus such as `--4` is rejected rather than read as 4.
+pub fn parse_expr(s: &str) -> Result<i128, PakxError> {
+    let mut toks: Vec<&str> = Vec::new();
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if matches!(c, '+' | '-' | '*') || c.is_whitespace() {
+            if let Some(st) = start.take() {
+                toks.push(&s[st..i]);
+            }
+            if !c.is_whitespace() {
+                toks.push(&s[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(st) = start {
+        toks.push(&s[st..]);
+    }
+
+    fn overflow(value: i128) -> PakxError {
+        PakxError::Overflow { value, bits: 128, signed: true, loc: None }
+    }
+    fn unary(s: &str, toks: &[&str], i: &mut usize) -> Result<i128, PakxError> {
+        let t = toks.get(*i).copied().unwrap_or("");
+        *i += 1;
+        if t == "-" {
+            if toks.get(*i) == Some(&"-") {
+                return parse_int(s);
+            }
+            let v = unary(s, toks, i)?;
+            return v.checked_neg().ok_or_else(|| overflow(v));
+        }
+        parse_int(t)
+    }
+    fn term(s: &str, toks: &[&str], i: &mut usize) -> Result<i128, PakxError> {
+        let mut v = unary(s, toks, i)?;
+        while toks.get(*i) == Some(&"*") {
+            *i += 1;
+            let r = unary(s, toks, i)?;
+            v = v.checked_mul(r).ok_or_else(|| overflow(v))?;
+        }
+        Ok(v)
+    }
+
+    let mut i = 0;
+    let mut v = term(s, &toks, &mut i)?;
+    while let Some(&op) = toks.get(i) {
+        if op != "+" && op != "-" {
+            return parse_int(s); // two literals in a row: report the whole thing
+        }
+        i += 1;
+        let r = term(s, &toks, &mut i)?;
+        v = if op == "+" { v.checked_add(r) } else { v.checked_sub(r) }.ok_or_else(|| overflow(v))?;
     }
+    Ok(v)
+}
+
+/// Parse an integer type name such as `u16` or `i64` into width and signedness.
+pub fn parse_int_type(t: &str) -> Option<(Width, bool)> {
+    let signed = match t.chars().next()? {
+        'u' => false,
+        'i' => true,
+        _ => return None,
+    };
+    let w = Width::new(t[1..].parse().ok()?).ok()?;
+    Some((w, signed))
+}
+
+pub fn pack_scalar(n: i128, width_bits: u32, endian: Endian, signed: bool, strict: bool) -> Result<Vec<u8>, PakxError> {
+    try_pack(n, Width::new(width_bits)?, endian, signed, strict)
 }
 
-pub fn pack_scalar(n: i128, width_bits: u32, endian: Endian, signed: bool, strict: bool) -> Result<Vec<u8>> {
-    let bytes = width_bytes(width_bits);
-    let bits = bytes * 8;
+pub fn try_pack(n: i128, width: Width, endian: Endian, signed: bool, strict: bool) -> Result<Vec<u8>, PakxError> {
+    let bytes = width.bytes();
+    let bits = width.bits();
 
     if strict {
         if signed {
-            let min = -((1i128) << (bits - 1));
-            let max = ((1i128) << (bits - 1)) - 1;
-            if n < min || n > max {
-                return Err(anyhow!("value {n} does not fit in signed {bits}-bit"));
+            // Every i128 fits in signed 128-bit.
+            if bits < 128 {
+                let min = -((1i128) << (bits - 1));
+                let max = ((1i128) << (bits - 1)) - 1;
+                if n < min || n > max {
+                    return Err(PakxError::Overflow { value: n, bits, signed: true, loc: None });
+                }
             }
         } else {
             if n < 0 {
-                return Err(anyhow!("negative value {n} not allowed for unsigned (use --signed or remove --strict)"));
+                return Err(PakxError::Negative { value: n, loc: None });
             }
-            let max = ((1i128) << bits) - 1;
-            if n > max {
-                return Err(anyhow!("value {n} does not fit in unsigned {bits}-bit"));
+            if bits < 128 && n > ((1i128) << bits) - 1 {
+                return Err(PakxError::Overflow { value: n, bits, signed: false, loc: None });
             }
         }
     }
@@ -77,9 +208,18 @@ pub fn pack_scalar(n: i128, width_bits: u32, endian: Endian, signed: bool, stric
     Ok(out)
 }
 
-pub fn unpack_scalar(bytes: &[u8], width_bits: u32, endian: Endian, signed: bool) -> i128 {
-    let len = width_bytes(width_bits);
-    assert_eq!(bytes.len(), len);
+/// `try_unpack` with the width given in bits.
+pub fn unpack_scalar(bytes: &[u8], width_bits: u32, endian: Endian, signed: bool) -> Result<i128, PakxError> {
+    try_unpack(bytes, Width::new(width_bits)?, endian, signed)
+}
+
+/// Decode the first `width.bytes()` bytes of `bytes`.
+pub fn try_unpack(bytes: &[u8], width: Width, endian: Endian, signed: bool) -> Result<i128, PakxError> {
+    let len = width.bytes();
+    if bytes.len() < len {
+        return Err(PakxError::Truncated { needed: len, got: bytes.len(), offset: 0 });
+    }
+    let bytes = &bytes[..len];
 
     let v = match endian {
         Endian::Little => {
@@ -94,104 +234,181 @@ pub fn unpack_scalar(bytes: &[u8], width_bits: u32, endian: Endian, signed: bool
         }
     };
 
-    if signed {
-        let bits = len * 8;
+    let bits = width.bits();
+    if signed && bits < 128 {
         let sign_bit = 1u128 << (bits - 1);
-        let mask = (1u128 << bits) - 1;
-        let val = v & mask;
-        if (val & sign_bit) != 0 {
+        if (v & sign_bit) != 0 {
             // negative -> sign extend
             let ext_mask = (!0u128) << bits;
-            (val | ext_mask) as i128
-        } else {
-            val as i128
+            return Ok((v | ext_mask) as i128);
         }
-    } else {
-        v as i128
     }
+    // At 128 bits the two's complement reinterpretation is the cast itself.
+    Ok(v as i128)
 }
 
-pub fn read_stdin_raw() -> Result<Vec<u8>> {
+pub fn read_raw<R: Read>(mut r: R) -> Result<Vec<u8>> {
     let mut b = Vec::new();
-    io::stdin().read_to_end(&mut b)?;
+    r.read_to_end(&mut b)?;
     Ok(b)
 }
 
-pub fn read_stdin_hex() -> Result<Vec<u8>> {
+pub fn read_hex<R: Read>(mut r: R) -> Result<Vec<u8>> {
     let mut s = String::new();
-    io::stdin().read_to_string(&mut s)?;
-    parse_hex_str(&s)
+    r.read_to_string(&mut s)?;
+    Ok(parse_hex_str(&s)?)
+}
+
+pub fn read_input<R: Read>(r: R, infmt: InFmt) -> Result<Vec<u8>> {
+    match infmt {
+        InFmt::Raw => read_raw(r),
+        InFmt::Hex => read_hex(r),
+    }
 }
 
-pub fn parse_hex_str(s: &str) -> Result<Vec<u8>> {
-    use anyhow::anyhow;
+pub fn read_stdin_raw() -> Result<Vec<u8>> {
+    read_raw(io::stdin().lock())
+}
+
+pub fn read_stdin_hex() -> Result<Vec<u8>> {
+    read_hex(io::stdin().lock())
+}
+
+pub fn parse_hex_str(s: &str) -> Result<Vec<u8>, PakxError> {
     let mut out = Vec::new();
+    let is_sep = |c: char| c.is_whitespace() || [':', ',', '-', ';'].contains(&c);
 
-    for raw in s.split(|c: char| c.is_whitespace() || [':', ',', '-', ';'].contains(&c)) {
-        if raw.is_empty() { continue; }
-        let t = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")).unwrap_or(raw);
+    let mut index = 0usize;
+    let mut line = 1usize;
+    let mut column = 1usize;
+    let mut start: Option<(usize, Loc)> = None;
 
-        if t.is_empty() { continue; }
-        if t.len() % 2 != 0 {
-            return Err(anyhow!("odd number of hex nibbles in token: {raw}"));
+    // A trailing separator flushes the last token.
+    for (off, c) in s.char_indices().chain(std::iter::once((s.len(), ' '))) {
+        if !is_sep(c) {
+            if start.is_none() {
+                start = Some((off, Loc { index, offset: off, line, column }));
+            }
+        } else if let Some((from, loc)) = start.take() {
+            parse_hex_token(&s[from..off], loc, &mut out)?;
+            index += 1;
         }
-        if !t.chars().all(|c| c.is_ascii_hexdigit()) {
-            return Err(anyhow!("non-hex digit in token: {raw}"));
+
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
         }
+    }
+    Ok(out)
+}
+
+fn parse_hex_token(raw: &str, loc: Loc, out: &mut Vec<u8>) -> Result<(), PakxError> {
+    let t = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")).unwrap_or(raw);
+
+    if t.len() % 2 != 0 {
+        return Err(PakxError::OddNibbles { token: raw.to_string(), loc });
+    }
+    if !t.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(PakxError::BadDigit { token: raw.to_string(), loc });
+    }
 
-        for i in (0..t.len()).step_by(2) {
-            let byte = u8::from_str_radix(&t[i..i+2], 16)?;
-            out.push(byte);
+    for i in (0..t.len()).step_by(2) {
+        // Both digits were checked above.
+        out.push(u8::from_str_radix(&t[i..i + 2], 16).unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Expand `\n`, `\r`, `\t`, `\0`, `\xHH` and `\\` escapes into bytes.
+pub fn unescape(s: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut it = s.chars();
+    while let Some(c) = it.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match it.next() {
+            Some('n') => out.push(b'\n'),
+            Some('r') => out.push(b'\r'),
+            Some('t') => out.push(b'\t'),
+            Some('0') => out.push(0),
+            Some('x') => {
+                let hex: String = it.by_ref().take(2).collect();
+                if hex.len() != 2 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(anyhow!("\\x needs exactly two hex digits in {s}"));
+                }
+                let b = u8::from_str_radix(&hex, 16)?;
+                out.push(b);
+            }
+            Some(c) => {
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            None => return Err(anyhow!("dangling backslash in {s}")),
         }
     }
     Ok(out)
 }
 
+/// One line of an `--explain` listing or offset map: offset, length and what
+/// the bytes are, in the same columns for every command.
+pub fn write_span(w: &mut dyn Write, offset: usize, len: usize, label: &str) -> Result<()> {
+    writeln!(w, "0x{offset:04x}  {len:5}  {label}")?;
+    Ok(())
+}
+
 pub fn write_bytes(outfmt: OutFmt, data: &[u8], sep: &str, uppercase: bool) -> Result<()> {
+    let mut w = io::stdout().lock();
+    write_bytes_to(&mut w, outfmt, data, sep, uppercase)
+}
+
+pub fn write_bytes_to<W: Write>(w: &mut W, outfmt: OutFmt, data: &[u8], sep: &str, uppercase: bool) -> Result<()> {
     match outfmt {
         OutFmt::Raw => {
-            let mut w = io::stdout().lock();
             w.write_all(data)?;
         }
         OutFmt::Hex => {
             let mut first = true;
             for b in data {
                 if !first {
-                    print!("{sep}");
+                    write!(w, "{sep}")?;
                 }
                 if uppercase {
-                    print!("{b:02X}");
+                    write!(w, "{b:02X}")?;
                 } else {
-                    print!("{b:02x}");
+                    write!(w, "{b:02x}")?;
                 }
                 first = false;
             }
-            println!();
+            writeln!(w)?;
         }
         OutFmt::C => {
             // \xHH\xHH...
             for b in data {
                 if uppercase {
-                    print!("\\x{b:02X}");
+                    write!(w, "\\x{b:02X}")?;
                 } else {
-                    print!("\\x{b:02x}");
+                    write!(w, "\\x{b:02x}")?;
                 }
             }
-            println!();
+            writeln!(w)?;
         }
         OutFmt::Py => {
-            print!("b\"");
+            write!(w, "b\"")?;
             for b in data {
                 if uppercase {
-                    print!("\\x{b:02X}");
+                    write!(w, "\\x{b:02X}")?;
                 } else {
-                    print!("\\x{b:02x}");
+                    write!(w, "\\x{b:02x}")?;
                 }
             }
-            println!("\"");
+            writeln!(w, "\"")?;
         }
     }
+    w.flush()?;
     Ok(())
 }
-
-
@@ -0,0 +1,71 @@
+use predicates::prelude::*;
+use pakx::cmd::addr::{parse_addr, Addr, AddrKind};
+use pakx::util::Endian;
+
+mod common;
+use common::bin;
+
+#[test]
+fn ipv4_and_port_in_network_order() {
+    let mut cmd = bin();
+    cmd.args(["addr", "pack", "192.168.1.1", "80", "--out", "hex"]);
+    cmd.assert().success().stdout("c0 a8 01 01 00 50\n");
+
+    let mut cmd = bin();
+    cmd.args(["addr", "pack", "192.168.1.1", "80", "--le", "--out", "hex"]);
+    cmd.assert().success().stdout("01 01 a8 c0 50 00\n");
+}
+
+#[test]
+fn ipv6_compression_and_mac_forms() {
+    let mut cmd = bin();
+    cmd.args(["addr", "pack", "2001:db8::1", "--out", "hex", "--sep", ""]);
+    cmd.assert().success().stdout("20010db8000000000000000000000001\n");
+
+    let dash = parse_addr("AA-BB-CC-DD-EE-FF").unwrap();
+    assert_eq!(dash, parse_addr("aa:bb:cc:dd:ee:ff").unwrap());
+    assert_eq!(dash.to_string(), "aa:bb:cc:dd:ee:ff");
+    assert!(matches!(parse_addr("::ffff:1.2.3.4").unwrap(), Addr::Ipv6(_)));
+}
+
+#[test]
+fn unpack_renders_canonical_text() {
+    let mut cmd = bin();
+    cmd.args(["addr", "unpack", "ipv6,ipv4,port", "--in", "hex"])
+        .write_stdin("20 01 0d b8 00 00 00 00 00 00 00 00 00 00 00 01  0a 00 00 01  01 bb");
+    cmd.assert().success().stdout("2001:db8::1\n10.0.0.1\n443\n");
+
+    let mut cmd = bin();
+    cmd.args(["addr", "unpack", "mac", "--le"]).write_stdin(&b"\xff\xee\xdd\xcc\xbb\xaa"[..]);
+    cmd.assert().success().stdout("aa:bb:cc:dd:ee:ff\n");
+}
+
+#[test]
+fn roundtrip_every_kind() {
+    for s in ["127.0.0.1", "fe80::1:2", "00:1b:44:11:3a:b7", "65535"] {
+        let a = parse_addr(s).unwrap();
+        for e in [Endian::Big, Endian::Little] {
+            assert_eq!(Addr::from_bytes(a.kind(), &a.to_bytes(e), e).unwrap(), a);
+        }
+        assert_eq!(a.to_string(), s);
+    }
+}
+
+#[test]
+fn rejects_malformed_values() {
+    let mut cmd = bin();
+    cmd.args(["addr", "pack", "1.2.3"]);
+    cmd.assert().failure().stderr(predicate::str::contains("not an IPv4, IPv6 or MAC address: 1.2.3"));
+
+    let mut cmd = bin();
+    cmd.args(["addr", "pack", "70000"]);
+    cmd.assert().failure().stderr(predicate::str::contains("port out of range"));
+
+    let mut cmd = bin();
+    cmd.args(["addr", "unpack", "ipv4"]).write_stdin("abcdef");
+    cmd.assert().failure().stderr(predicate::str::contains("truncated input at offset 4"));
+
+    assert!(parse_addr("+a:bb:cc:dd:ee:ff").is_err());
+    assert!(Addr::from_bytes(AddrKind::Ipv4, &[1, 2, 3], Endian::Big).is_err());
+    assert!(Addr::from_bytes(AddrKind::Port, &[1, 2, 3], Endian::Big).is_err());
+}
@@ -0,0 +1,50 @@
+use predicates::prelude::*;
+use pakx::api::parse_item;
+use pakx::util::{Endian, Width};
+use pakx::Packer;
+
+mod common;
+use common::bin;
+
+#[test]
+fn natural_alignment_pads_each_value() {
+    let p = Packer::new(Width::W32).endian(Endian::Big).natural(true);
+    let packed = p.pack_layout(&["u8:1", "2", "u16:3"]).unwrap();
+    assert_eq!(packed.bytes, [1, 0, 0, 0, 0, 0, 0, 2, 0, 3]);
+    assert_eq!(packed.padding, vec![(1..4)]);
+    assert_eq!(packed.values[1], (1, 4..8));
+}
+
+#[test]
+fn total_alignment_and_pad_byte() {
+    let p = Packer::new(Width::W8).align(Some(4)).pad_byte(0x90);
+    assert_eq!(p.pack(&["1", "2", "3", "4", "5"]).unwrap(), [1, 2, 3, 4, 5, 0x90, 0x90, 0x90]);
+}
+
+#[test]
+fn typed_items_parse() {
+    assert_eq!(parse_item("i16:-2").unwrap().ty, Some((Width::W16, true)));
+    assert_eq!(parse_item("0x10").unwrap().ty, None);
+    assert!(parse_item("u24:1").is_err());
+}
+
+#[test]
+fn explain_reports_padding_on_stderr() {
+    let mut cmd = bin();
+    cmd.args(["pack", "--width", "32", "--natural", "--explain", "--out", "hex", "u8:1", "2"]);
+    cmd.assert()
+        .success()
+        .stdout("01 00 00 00 02 00 00 00\n")
+        .stderr(predicate::str::contains("0x0001      3  padding"));
+}
+
+#[test]
+fn align_takes_expressions_and_is_capped() {
+    let mut cmd = bin();
+    cmd.args(["p8", "1", "--align", "0x4", "--out", "hex"]);
+    cmd.assert().success().stdout("01 00 00 00\n");
+
+    let mut cmd = bin();
+    cmd.args(["p8", "1", "--align", "0xffffffffffffffff"]);
+    cmd.assert().failure().stderr(predicate::str::contains("exceeds the 268435456-byte limit"));
+}
@@ -0,0 +1,34 @@
+use pakx::util::{Endian, InFmt, OutFmt, Width};
+use pakx::{Formatter, Packer, Unpacker};
+
+#[test]
+fn packer_builder_packs_and_repeats() {
+    let p = Packer::new(Width::W16).endian(Endian::Big).repeat(Some(2));
+    assert_eq!(p.pack(&["0x4142", "0x4344"]).unwrap(), b"ABCDABCD");
+}
+
+#[test]
+fn packer_strict_rejects_overflow() {
+    let p = Packer::new(Width::W8).strict(true);
+    assert!(p.pack_ints(&[0x1ff]).is_err());
+    assert_eq!(Packer::new(Width::W8).pack_ints(&[0x1ff]).unwrap(), [0xff]);
+}
+
+#[test]
+fn formatter_writes_to_any_sink() {
+    let f = Formatter::new(OutFmt::Hex).sep(":").uppercase(true);
+    let mut out = Vec::new();
+    f.write(&mut out, &[0xde, 0xad]).unwrap();
+    assert_eq!(out, b"DE:AD\n");
+    assert_eq!(Formatter::new(OutFmt::Py).format(b"A").unwrap(), b"b\"\\x41\"\n");
+    assert_eq!(Formatter::default(), Formatter::new(OutFmt::Raw));
+}
+
+#[test]
+fn unpacker_reads_from_any_source() {
+    let u = Unpacker::new(Width::W16).endian(Endian::Big).signed(true).input(InFmt::Hex);
+    let mut out = Vec::new();
+    u.run("ff ff 00 2a ff".as_bytes(), &mut out).unwrap();
+    assert_eq!(out, b"-1\n42\n");
+    assert_eq!(Unpacker::new(Width::W8).count(Some(2)).unpack(&[1, 2, 3]).unwrap(), [1, 2]);
+}
@@ -0,0 +1,74 @@
+use predicates::prelude::*;
+use pakx::api::BadChars;
+use pakx::util::OutFmt;
+use pakx::{Formatter, PakxError};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::bin;
+
+#[test]
+fn pack_refuses_bad_bytes_and_names_the_value() {
+    let mut cmd = bin();
+    cmd.args(["p32", "--badchars", "00 0a 0d 20", "0x41414141", "0x0a424242"]);
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("output contains 1 bad byte(s)"))
+        .stderr(predicate::str::contains("offset 0x0007: 0x0a from value 1 (0x0a424242)"));
+}
+
+#[test]
+fn pack_clean_output_passes() {
+    let mut cmd = bin();
+    cmd.args(["p16", "--be", "--badchars", "00", "--out", "hex", "0x4142"]);
+    cmd.assert().success().stdout("41 42\n");
+}
+
+#[test]
+fn warn_mode_still_writes_output() {
+    let mut cmd = bin();
+    cmd.args(["p16", "--badchars", "00", "--badchars-warn", "--align", "4", "--out", "hex", "1"]);
+    cmd.assert()
+        .success()
+        .stdout("01 00 00 00\n")
+        .stderr(predicate::str::contains("warning: output contains 3 bad byte(s)"))
+        .stderr(predicate::str::contains("offset 0x0002: 0x00 from padding"));
+}
+
+#[test]
+fn flat_and_bytes_check_too() {
+    let mut cmd = bin();
+    cmd.args(["flat", "-q", "--badchars", "0d", "str:AB", "hex:0d"]);
+    cmd.assert().failure().stderr(predicate::str::contains("offset 0x0002: 0x0d from item 1 (hex:0d)"));
+
+    let mut cmd = bin();
+    cmd.args(["bytes", "--badchars", "20"]).write_stdin("41 20");
+    cmd.assert().failure().stderr(predicate::str::contains("offset 0x0001: 0x20"));
+}
+
+#[test]
+fn formatter_reports_every_hit() {
+    let fmt = Formatter::new(OutFmt::Hex).badchars(Some(BadChars::new(b"\x00\n")));
+    let err = fmt.format(b"A\0B\n").unwrap_err();
+    match err.downcast_ref::<PakxError>() {
+        Some(PakxError::BadChars { hits }) => {
+            assert_eq!(hits.iter().map(|h| (h.offset, h.byte)).collect::<Vec<_>>(), [(1, 0), (3, b'\n')]);
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn warnings_go_to_the_hook_not_stderr() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let bc = BadChars::new(b"\0").warn(true).on_warning(move |e| sink.lock().unwrap().push(e.to_string()));
+    let out = Formatter::new(OutFmt::Hex).badchars(Some(bc)).format(b"A\0").unwrap();
+    assert_eq!(out, b"41 00\n");
+    assert_eq!(*seen.lock().unwrap(), ["output contains 1 bad byte(s):\n  offset 0x0001: 0x00"]);
+
+    // Without a hook, warn mode is silent.
+    let quiet = Formatter::new(OutFmt::Hex).badchars(Some(BadChars::new(b"\0").warn(true)));
+    assert_eq!(quiet.format(b"\0").unwrap(), b"00\n");
+}
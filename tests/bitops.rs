@@ -0,0 +1,67 @@
+use predicates::prelude::*;
+use pakx::cmd::bitops::{apply_word, transform, BitOp};
+use pakx::util::{Endian, Width};
+use proptest::prelude::*;
+
+mod common;
+use common::bin;
+
+#[test]
+fn word_ops() {
+    assert_eq!(apply_word(BitOp::Reverse, 0x01, Width::W8), 0x80);
+    assert_eq!(apply_word(BitOp::Reverse, 0x0001, Width::W16), 0x8000);
+    assert_eq!(apply_word(BitOp::Reverse, 1, Width::W128), 1 << 127);
+    assert_eq!(apply_word(BitOp::SwapNibbles, 0x1234, Width::W16), 0x2143);
+    assert_eq!(apply_word(BitOp::Rol(4), 0x1234_5678, Width::W32), 0x2345_6781);
+    assert_eq!(apply_word(BitOp::Ror(4), 0x1234_5678, Width::W32), 0x8123_4567);
+    assert_eq!(apply_word(BitOp::Rol(33), 0x8000_0000, Width::W32), 1);
+}
+
+#[test]
+fn stream_honours_width_and_endianness() {
+    assert_eq!(transform(BitOp::Reverse, &[0x01, 0x00], Width::W16, Endian::Little).unwrap(), [0x00, 0x80]);
+    assert_eq!(transform(BitOp::Reverse, &[0x01, 0x00], Width::W16, Endian::Big).unwrap(), [0x00, 0x80]);
+    assert_eq!(transform(BitOp::Rol(8), &[0x01, 0x02], Width::W16, Endian::Big).unwrap(), [0x02, 0x01]);
+
+    let mut cmd = bin();
+    cmd.args(["bits", "rev", "--in", "hex", "--out", "hex"]).write_stdin("01 02 03");
+    cmd.assert().success().stdout("80 40 c0\n");
+}
+
+#[test]
+fn scalar_value_is_packed_first() {
+    let mut cmd = bin();
+    cmd.args(["bits", "rol", "--by", "4", "--width", "32", "--be", "--out", "hex", "0x12345678"]);
+    cmd.assert().success().stdout("23 45 67 81\n");
+}
+
+#[test]
+fn scalar_value_must_fit_the_width() {
+    let mut cmd = bin();
+    cmd.args(["bits", "rev", "--width", "16", "--out", "hex", "0xffff"]);
+    cmd.assert().success().stdout("ff ff\n");
+
+    let mut cmd = bin();
+    cmd.args(["bits", "rev", "--width", "16", "--out", "hex", "--", "-2"]);
+    cmd.assert().success().stdout("ff 7f\n");
+
+    let mut cmd = bin();
+    cmd.args(["bits", "rev", "--width", "16", "0x1ffff"]);
+    cmd.assert().failure().stderr(predicate::str::contains("does not fit in unsigned 16-bit"));
+}
+
+#[test]
+fn partial_word_is_an_error() {
+    let mut cmd = bin();
+    cmd.args(["bits", "nibswap", "--width", "16"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("truncated input at offset 2"));
+}
+
+proptest! {
+    #[test]
+    fn rotations_and_reversal_invert(v in any::<u64>(), n in 0u32..200) {
+        let w = Width::W64;
+        prop_assert_eq!(apply_word(BitOp::Ror(n), apply_word(BitOp::Rol(n), v as u128, w), w), v as u128);
+        prop_assert_eq!(apply_word(BitOp::Reverse, apply_word(BitOp::Reverse, v as u128, w), w), v as u128);
+    }
+}
@@ -0,0 +1,67 @@
+use predicates::prelude::*;
+
+mod common;
+use common::bin;
+
+#[test]
+fn bits_pack_lsb0_be() {
+    let mut cmd = bin();
+    cmd.args(["bits", "pack", "--width", "32", "--be", "--out", "hex", "3:0b101", "5:2", "8:0xff"]);
+    cmd.assert().success().stdout("00 00 ff 15\n");
+}
+
+#[test]
+fn bits_pack_msb0() {
+    let mut cmd = bin();
+    cmd.args(["bits", "pack", "--width", "16", "--msb0", "--be", "--out", "hex", "4:0xa", "4:0xb", "8:0xcd"]);
+    cmd.assert().success().stdout("ab cd\n");
+}
+
+#[test]
+fn bits_pack_field_overflow_errors() {
+    let mut cmd = bin();
+    cmd.args(["bits", "pack", "--width", "8", "3:8"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("does not fit in unsigned 3-bit"));
+}
+
+#[test]
+fn bits_unpack_fields() {
+    let mut cmd = bin();
+    cmd.args(["bits", "unpack", "--width", "32", "--be", "--in", "hex", "--fields", "3,5,8,16"]);
+    cmd.write_stdin("00 00 ff 15\n");
+    cmd.assert().success().stdout("5 2 255 0\n");
+}
+
+#[test]
+fn bits_unpack_signed_msb0() {
+    let mut cmd = bin();
+    cmd.args(["bits", "unpack", "--width", "8", "--msb0", "--signed", "--in", "hex", "--fields", "4,4"]);
+    cmd.write_stdin("f7\n");
+    cmd.assert().success().stdout("-1 7\n");
+}
+
+#[test]
+fn bits_huge_field_lengths_error() {
+    let mut cmd = bin();
+    cmd.args(["bits", "pack", "--width", "8", "4294967295:0", "1:0"]);
+    cmd.assert().failure().stderr(predicate::str::contains("fields use 4294967296 bits but width is 8"));
+
+    let mut cmd = bin();
+    cmd.args(["bits", "unpack", "--width", "8", "--in", "hex", "--fields", "4294967295,1"]);
+    cmd.write_stdin("00\n");
+    cmd.assert().failure().stderr(predicate::str::contains("fields use 4294967296 bits but width is 8"));
+}
+
+#[test]
+fn bits_family_defaults_to_hex_like_bswap() {
+    let mut cmd = bin();
+    cmd.args(["bits", "pack", "--width", "8", "4:1", "4:2"]);
+    cmd.assert().success().stdout("21\n");
+
+    let mut cmd = bin();
+    cmd.args(["bits", "rol", "--by", "1"]);
+    cmd.write_stdin("A");
+    cmd.assert().success().stdout("82\n");
+}
@@ -0,0 +1,53 @@
+use predicates::prelude::*;
+use pakx::cmd::byteops::{apply_op, brute_xor, ByteOp, Key};
+
+mod common;
+use common::bin;
+
+#[test]
+fn xor_with_multibyte_hex_key() {
+    let mut cmd = bin();
+    cmd.args(["xor", "--in", "hex", "--out", "hex", "--key-hex", "de ad"]).write_stdin("00 00 ff ff de");
+    cmd.assert().success().stdout("de ad 21 52 00\n");
+}
+
+#[test]
+fn ops_wrap_mod_256_and_roll() {
+    let k = Key::new(vec![0x01, 0x02]).roll(1);
+    assert_eq!(apply_op(ByteOp::Add, b"abcd", &k).unwrap(), [b'b', b'd', b'e', b'g']);
+    assert_eq!(apply_op(ByteOp::Sub, &[0x00], &Key::new(vec![1])).unwrap(), [0xff]);
+    assert_eq!(apply_op(ByteOp::And, &[0xf3], &Key::new(vec![0x0f])).unwrap(), [0x03]);
+    assert_eq!(apply_op(ByteOp::Or, &[0x30], &Key::new(vec![0x01])).unwrap(), [0x31]);
+    assert_eq!(apply_op(ByteOp::Not, &[0x0f], &Key::default()).unwrap(), [0xf0]);
+    // Subtracting with the same rolling key undoes the addition.
+    let enc = apply_op(ByteOp::Add, b"firmware", &k).unwrap();
+    assert_eq!(apply_op(ByteOp::Sub, &enc, &k).unwrap(), b"firmware");
+}
+
+#[test]
+fn key_file_is_read_raw() {
+    let dir = common::scratch("byteops");
+    let key = dir.join("key.bin");
+    std::fs::write(&key, [0x20]).unwrap();
+    let mut cmd = bin();
+    cmd.args(["xor", "--key-file", key.to_str().unwrap()]).write_stdin("hELLO");
+    cmd.assert().success().stdout("Hello");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn brute_ranks_the_real_key_first() {
+    let secret: Vec<u8> = b"The quick brown fox jumps over the lazy dog".iter().map(|b| b ^ 0x5a).collect();
+    assert_eq!(brute_xor(&secret)[0].0, 0x5a);
+
+    let mut cmd = bin();
+    cmd.args(["xor", "--brute", "--top", "1"]).write_stdin(secret);
+    cmd.assert().success().stdout(predicate::str::starts_with("0x5a  100.0%  The quick brown fox"));
+}
+
+#[test]
+fn missing_key_is_an_error() {
+    let mut cmd = bin();
+    cmd.args(["or"]).write_stdin("a");
+    cmd.assert().failure().stderr(predicate::str::contains("one of --key, --key-hex or --key-file is required"));
+}
@@ -34,3 +34,13 @@ fn unpack_hex_bad_byte_errors() {
                .or(predicate::str::contains("bad hex byte")),
        );
 }
+
+#[test]
+fn hex_error_reports_position() {
+    let mut cmd = bin();
+    cmd.args(["bytes", "--in", "hex"]);
+    cmd.write_stdin("de ad\nbe eg\n");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("token 3 at line 2, column 4 (offset 9)"));
+}
@@ -116,4 +116,12 @@ fn c_and_py_formats() {
     let mut pyfmt = bin();
     pyfmt.args(["p16", "--be", "0x4142", "--out", "py"]);
     pyfmt.assert().success().stdout("b\"\\x41\\x42\"\n");
-}
\ No newline at end of file
+}
+
+#[test]
+fn u128_signed_decodes_negative() {
+    let mut cmd = bin();
+    cmd.args(["u128", "--signed", "--in", "hex"]);
+    cmd.write_stdin("ff ff ff ff ff ff ff ff ff ff ff ff ff ff ff ff\n");
+    cmd.assert().success().stdout("-1\n");
+}
@@ -0,0 +1,52 @@
+use predicates::prelude::*;
+use pakx::cmd::conv::{convert, describe};
+use pakx::util::{parse_int_type, Endian};
+
+mod common;
+use common::bin;
+
+fn ty(s: &str) -> (pakx::util::Width, bool) { parse_int_type(s).unwrap() }
+
+#[test]
+fn sign_extends_signed_sources() {
+    let mut cmd = bin();
+    cmd.args(["conv", "--from", "i16", "--to", "u64", "0xff80"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("from  i16   -128  (0xff80)"))
+        .stdout(predicate::str::contains("to    u64   18446744073709551488  (0xffffffffffffff80)"))
+        .stdout(predicate::str::contains("op    sign-extend, value changed"))
+        .stdout(predicate::str::contains("le    80 ff ff ff ff ff ff ff"));
+}
+
+#[test]
+fn zero_extends_unsigned_sources_in_big_endian() {
+    let mut cmd = bin();
+    cmd.args(["conv", "--from", "u16", "--to", "i64", "--be", "0xff80"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("op    zero-extend\n"))
+        .stdout(predicate::str::contains("be    00 00 00 00 00 00 ff 80"));
+}
+
+#[test]
+fn truncation_and_reinterpretation() {
+    let c = convert(0x1ff, ty("u32"), ty("i8"), Endian::Little).unwrap();
+    assert_eq!((c.input, c.output, c.bytes), (511, -1, vec![0xff]));
+    let c = convert(200, ty("u8"), ty("i8"), Endian::Little).unwrap();
+    assert_eq!(c.output, -56);
+    assert_eq!(describe(ty("u32"), ty("i8")), "truncate");
+    assert_eq!(describe(ty("u8"), ty("i8")), "reinterpret");
+    assert_eq!(describe(ty("i32"), ty("i32")), "identity");
+}
+
+#[test]
+fn u128_prints_unsigned_and_bad_types_fail() {
+    let mut cmd = bin();
+    cmd.args(["conv", "--from", "i128", "--to", "u128", "-1"]);
+    cmd.assert().success().stdout(predicate::str::contains("u128  340282366920938463463374607431768211455"));
+
+    let mut cmd = bin();
+    cmd.args(["conv", "--from", "f32", "--to", "u8", "1"]);
+    cmd.assert().failure().stderr(predicate::str::contains("expected a type such as u8"));
+}
@@ -0,0 +1,114 @@
+use predicates::prelude::*;
+use pakx::cstruct::{Abi, Header};
+use pakx::json::Json;
+use pakx::util::Endian;
+
+mod common;
+use common::bin;
+
+const HDR: &str = r#"
+#include <stdint.h>
+struct inner { uint16_t a; uint8_t b; };
+typedef struct {
+    uint8_t kind;       /* 7 bytes of padding follow on LP64 */
+    long value;
+    char tag[3];
+    struct inner in;
+} rec_t;
+
+#pragma pack(push, 1)
+struct tight { uint8_t a; uint32_t b; };
+#pragma pack(pop)
+struct loose { uint8_t a; uint32_t b; };
+struct attr { uint8_t a; uint32_t b; } __attribute__((packed));
+"#;
+
+fn offsets(h: &Header, name: &str) -> (Vec<usize>, usize) {
+    let l = h.get(name).unwrap();
+    (l.members.iter().map(|m| m.offset).collect(), l.size)
+}
+
+#[test]
+fn lp64_and_ilp32_offsets() {
+    let lp64 = Header::parse(HDR, Abi::Lp64).unwrap();
+    assert_eq!(offsets(&lp64, "rec_t"), (vec![0, 8, 16, 20], 24));
+    let ilp32 = Header::parse(HDR, Abi::Ilp32).unwrap();
+    assert_eq!(offsets(&ilp32, "rec_t"), (vec![0, 4, 8, 12], 16));
+}
+
+#[test]
+fn pragma_pack_and_packed_attribute() {
+    let h = Header::parse(HDR, Abi::Lp64).unwrap();
+    assert_eq!(offsets(&h, "tight"), (vec![0, 1], 5));
+    assert_eq!(offsets(&h, "loose"), (vec![0, 4], 8));
+    assert_eq!(offsets(&h, "attr"), (vec![0, 1], 5));
+}
+
+#[test]
+fn layout_decodes_named_fields() {
+    let h = Header::parse(HDR, Abi::Ilp32).unwrap();
+    let schema = h.to_schema(h.pick(Some("rec_t")).unwrap(), Endian::Little).unwrap();
+    let data = [1, 0, 0, 0, 0x2a, 0, 0, 0, b'h', b'i', 0, 0, 5, 0, 6, 0];
+    let rec = schema.decode(&data).unwrap();
+    assert_eq!(rec.get("value").and_then(|v| v.get("value")), Some(&Json::Int(42)));
+    assert_eq!(rec.get("tag").and_then(|v| v.get("value")), Some(&Json::Str("hi".into())));
+    let inner = rec.get("in").and_then(|v| v.get("value")).unwrap();
+    assert_eq!(inner.get("b").and_then(|v| v.get("offset")), Some(&Json::Int(14)));
+}
+
+#[test]
+fn typedef_aliases_resolve() {
+    let src = "typedef struct foo { uint8_t a; uint16_t b; } foo_t;\n\
+               struct bar;\ntypedef struct bar bar_t;\nstruct bar { foo_t f; uint8_t c; };\n";
+    let h = Header::parse(src, Abi::Lp64).unwrap();
+    assert_eq!(h.get("foo_t").unwrap().name, "foo");
+    assert_eq!(offsets(&h, "bar_t"), (vec![0, 4], 6));
+    assert_eq!(h.pick(Some("foo_t")).unwrap().size, 4);
+    assert!(h.pick(Some("baz_t")).is_err());
+}
+
+#[test]
+fn member_aligned_attribute() {
+    let src = "struct a { uint8_t x; uint32_t y __attribute__((aligned(16))); };\n\
+               struct b { uint8_t x; uint32_t y __attribute__((aligned(8))); } __attribute__((packed));\n\
+               struct c { uint8_t x; uint32_t y __attribute__((packed)); };\n";
+    let h = Header::parse(src, Abi::Lp64).unwrap();
+    assert_eq!(offsets(&h, "a"), (vec![0, 16], 32));
+    assert_eq!(offsets(&h, "b"), (vec![0, 8], 16));
+    assert_eq!(offsets(&h, "c"), (vec![0, 1], 5));
+    assert!(Header::parse("struct d { int x __attribute__((aligned(3))); };", Abi::Lp64).is_err());
+}
+
+#[test]
+fn packed_pointer_keeps_its_width() {
+    let h = Header::parse("struct s { uint8_t a; void *p __attribute__((packed)); uint32_t b; };", Abi::Lp64).unwrap();
+    assert_eq!(offsets(&h, "s"), (vec![0, 1, 12], 16));
+    let schema = h.to_schema(h.pick(Some("s")).unwrap(), Endian::Little).unwrap();
+    let mut data = [0u8; 16];
+    data[1..9].copy_from_slice(&0x1122334455667788u64.to_le_bytes());
+    data[12] = 7;
+    let rec = schema.decode(&data).unwrap();
+    assert_eq!(rec.get("p").and_then(|v| v.get("value")), Some(&Json::Int(0x1122334455667788)));
+    assert_eq!(rec.get("b").and_then(|v| v.get("offset")), Some(&Json::Int(12)));
+}
+
+#[test]
+fn unsupported_constructs_error() {
+    assert!(Header::parse("struct s { int a : 3; };", Abi::Lp64).is_err());
+    assert!(Header::parse("struct s { union { int a; } u; };", Abi::Lp64).is_err());
+}
+
+#[test]
+fn cstruct_show_cli() {
+    let dir = common::scratch("cstruct");
+    let header = dir.join("h.h");
+    std::fs::write(&header, "struct s { uint8_t a; uint32_t b; };\n").unwrap();
+
+    let mut cmd = bin();
+    cmd.args(["cstruct", "--show"]).arg(&header);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("struct s (LP64): size 8, align 4"))
+        .stdout(predicate::str::contains("0x0001     3  <padding>"));
+    std::fs::remove_dir_all(&dir).ok();
+}
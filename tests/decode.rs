@@ -0,0 +1,82 @@
+use predicates::prelude::*;
+use pakx::json::Json;
+use pakx::schema::Schema;
+
+mod common;
+use common::bin;
+
+const HDR: &str = r#"
+endian le
+magic: bytes[2] = "PK"
+count: u8
+entries: Entry[count]   # counted array of structs
+
+struct Entry {
+    kind: u8
+    value: i16be
+}
+"#;
+
+fn field<'a>(rec: &'a Json, name: &str) -> &'a Json {
+    rec.get(name).unwrap()
+}
+
+#[test]
+fn decode_counted_structs_with_offsets() {
+    let schema = Schema::parse(HDR).unwrap();
+    let rec = schema.decode(b"PK\x02\x01\xff\xfe\x02\x00\x10").unwrap();
+
+    let entries = field(&rec, "entries");
+    assert_eq!(entries.get("offset"), Some(&Json::Int(3)));
+    assert_eq!(entries.get("size"), Some(&Json::Int(6)));
+    let Some(Json::Array(items)) = entries.get("value") else { panic!("not an array") };
+    assert_eq!(field(&items[0], "value").get("value"), Some(&Json::Int(-2)));
+    assert_eq!(field(&items[1], "value").get("offset"), Some(&Json::Int(7)));
+}
+
+#[test]
+fn decode_magic_mismatch_and_truncation() {
+    let schema = Schema::parse(HDR).unwrap();
+    let err = schema.decode(b"ZZ\x00").unwrap_err().to_string();
+    assert!(err.contains("magic mismatch"), "{err}");
+    let err = schema.decode(b"PK\x01\x01\xff").unwrap_err().to_string();
+    assert!(err.contains("truncated input at offset 4"), "{err}");
+}
+
+#[test]
+fn schema_errors_name_the_line() {
+    let err = Schema::parse("a: u8\nb: u24\n").unwrap_err().to_string();
+    assert!(err.starts_with("schema line 2:"), "{err}");
+    let err = Schema::parse("xs: u8[n]\n").unwrap_err().to_string();
+    assert!(err.contains("must name an earlier integer field"), "{err}");
+}
+
+#[test]
+fn schema_rejects_ambiguous_or_unbounded_layouts() {
+    let err = Schema::parse("a: u8\n_: pad[1]\n_: pad[1]\na: u16\n").unwrap_err().to_string();
+    assert_eq!(err, "schema line 4: duplicate field name a");
+    let err = Schema::parse("magic: u8 = 0x1ff\n").unwrap_err().to_string();
+    assert!(err.contains("constant for magic: value 511 does not fit in unsigned 8-bit"), "{err}");
+    assert!(Schema::parse("magic: i8 = -1\n").unwrap().decode(&[0xff]).is_ok());
+
+    let schema = Schema::parse("xs: Empty[0x10000000]\nstruct Empty {\n_: pad[0]\n}\n").unwrap();
+    let err = schema.decode(&[]).unwrap_err().to_string();
+    assert!(err.contains("268435456 elements of size 0"), "{err}");
+}
+
+#[test]
+fn decode_cli_reads_file() {
+    let dir = common::scratch("decode");
+    let schema = dir.join("rec.pakx");
+    std::fs::write(&schema, "id: u16be\nname: string[4]\nratio: f32\n").unwrap();
+
+    let mut cmd = bin();
+    cmd.args(["decode", "--in", "hex", "--schema"]).arg(&schema);
+    cmd.write_stdin("01 02 68 69 00 00 00 00 c0 3f\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"value\": 258"))
+        .stdout(predicate::str::contains("\"value\": \"hi\""))
+        .stdout(predicate::str::contains("\"value\": 1.5"));
+    std::fs::remove_dir_all(&dir).ok();
+}
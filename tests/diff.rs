@@ -0,0 +1,77 @@
+use predicates::prelude::*;
+use pakx::cmd::diff::{changed_words, diff_ranges};
+use std::path::PathBuf;
+
+mod common;
+use common::bin;
+
+fn files(name: &str, a: &[u8], b: &[u8]) -> (PathBuf, PathBuf) {
+    let dir = common::scratch(&format!("diff-{name}"));
+    let (pa, pb) = (dir.join("a.bin"), dir.join("b.bin"));
+    std::fs::write(&pa, a).unwrap();
+    std::fs::write(&pb, b).unwrap();
+    (pa, pb)
+}
+
+#[test]
+fn ranges_merge_adjacent_bytes() {
+    assert_eq!(diff_ranges(b"abcdef", b"aXYdeZ"), vec![(1..3), (5..6)]);
+    assert!(diff_ranges(b"same", b"same").is_empty());
+}
+
+#[test]
+fn words_are_decoded_with_width_and_endianness() {
+    let mut a = vec![0u8; 0x48];
+    let mut b = a.clone();
+    a[0x40..0x44].copy_from_slice(&1000u32.to_be_bytes());
+    b[0x40..0x44].copy_from_slice(&2000u32.to_be_bytes());
+    let (pa, pb) = files("words", &a, &b);
+    let mut cmd = bin();
+    cmd.args(["diff", pa.to_str().unwrap(), pb.to_str().unwrap(), "--width", "32", "--be"]);
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains("0x00000042  2 byte(s) differ"))
+        .stdout(predicate::str::contains("03 e8"))
+        .stdout(predicate::str::contains("07 d0"))
+        .stdout(predicate::str::contains("offset 0x40: 1000 -> 2000"));
+    std::fs::remove_dir_all(pa.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn exit_status_follows_cmp() {
+    let (pa, pb) = files("status", b"abc", b"abc");
+    let mut cmd = bin();
+    cmd.args(["diff", pa.to_str().unwrap(), pb.to_str().unwrap()]);
+    cmd.assert().code(0).stdout("");
+
+    std::fs::write(&pb, b"abd").unwrap();
+    let mut cmd = bin();
+    cmd.args(["diff", "-s", pa.to_str().unwrap(), pb.to_str().unwrap()]);
+    cmd.assert().code(1).stdout("");
+
+    let mut cmd = bin();
+    cmd.args(["diff", pa.to_str().unwrap(), "/nonexistent/pakx"]);
+    cmd.assert().code(2).stderr(predicate::str::contains("cannot read /nonexistent/pakx"));
+    std::fs::remove_dir_all(pa.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn size_difference_alone_counts() {
+    let (pa, pb) = files("size", b"abc", b"abcd");
+    let mut cmd = bin();
+    cmd.args(["diff", pa.to_str().unwrap(), pb.to_str().unwrap()]);
+    cmd.assert().code(1).stdout(predicate::str::contains("is 3 bytes").and(predicate::str::contains("is 4 bytes")));
+    std::fs::remove_dir_all(pa.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn word_touched_by_two_ranges_prints_once() {
+    let (pa, pb) = files("once", &[0, 0, 0, 0], &[1, 0, 1, 0]);
+    let mut cmd = bin();
+    cmd.args(["diff", pa.to_str().unwrap(), pb.to_str().unwrap(), "--width", "32", "--le"]);
+    let out = cmd.assert().code(1).get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("offset 0x0: 0 -> 65537").count(), 1, "{out}");
+    assert_eq!(changed_words(&[0..1, 2..3, 5..6], 4, 8), [0, 4]);
+    std::fs::remove_dir_all(pa.parent().unwrap()).unwrap();
+}
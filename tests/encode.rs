@@ -0,0 +1,90 @@
+use predicates::prelude::*;
+use pakx::json::Json;
+use pakx::schema::Schema;
+
+mod common;
+use common::bin;
+
+const MSG: &str = r#"
+endian be
+magic: u16 = 0xcafe
+len: u8
+payload: bytes[len]
+n: u8
+items: Item[n]
+
+struct Item {
+    id: u8
+    delta: i16le
+}
+"#;
+
+fn encode(src: &str) -> anyhow::Result<Vec<u8>> {
+    Schema::parse(MSG).unwrap().encode(&Json::parse(src).unwrap())
+}
+
+#[test]
+fn encode_fills_constants_and_lengths() {
+    let b = encode(r#"{"payload": "de ad be", "items": [{"id": 1, "delta": -2}]}"#).unwrap();
+    assert_eq!(b, [0xca, 0xfe, 3, 0xde, 0xad, 0xbe, 1, 1, 0xfe, 0xff]);
+}
+
+#[test]
+fn encode_range_and_length_checks() {
+    let err = encode(r#"{"payload": "", "items": [{"id": 256, "delta": 0}]}"#).unwrap_err().to_string();
+    assert!(err.starts_with("items[0].id: value 256 does not fit in unsigned 8-bit"), "{err}");
+
+    let err = encode(r#"{"len": 2, "payload": "de", "items": []}"#).unwrap_err().to_string();
+    assert!(err.contains("len: count must be 1"), "{err}");
+
+    let err = encode(r#"{"magic": 1, "payload": "", "items": []}"#).unwrap_err().to_string();
+    assert!(err.contains("does not match constant"), "{err}");
+}
+
+#[test]
+fn decode_output_round_trips() {
+    let schema = Schema::parse(MSG).unwrap();
+    let data = [0xca, 0xfe, 1, 0x41, 2, 7, 0, 0x80, 8, 0xff, 0x7f];
+    let decoded = schema.decode(&data).unwrap();
+    assert_eq!(schema.encode(&decoded).unwrap(), data);
+}
+
+#[test]
+fn fields_named_like_decode_output_are_not_unwrapped() {
+    let flat = Schema::parse("size: u8\nvalue: bytes[size]\n").unwrap();
+    assert_eq!(flat.encode(&Json::parse(r#"{"size": 2, "value": "aa bb"}"#).unwrap()).unwrap(), [2, 0xaa, 0xbb]);
+
+    let nested = Schema::parse("rec: Rec\nstruct Rec {\noffset: u8\nsize: u8\nvalue: bytes[size]\n}\n").unwrap();
+    let bare = Json::parse(r#"{"rec": {"offset": 9, "size": 1, "value": "cc"}}"#).unwrap();
+    assert_eq!(nested.encode(&bare).unwrap(), [9, 1, 0xcc]);
+    let decoded = nested.decode(&[9, 2, 0xaa, 0xbb]).unwrap();
+    assert_eq!(nested.encode(&decoded).unwrap(), [9, 2, 0xaa, 0xbb]);
+}
+
+#[test]
+fn huge_counts_are_errors() {
+    let err = Schema::parse("_: pad[0xffffffffffffffff]\n").unwrap_err().to_string();
+    assert!(err.contains("exceeds the 268435456-byte limit"), "{err}");
+
+    let schema = Schema::parse("n: u64\n_: pad[n]\n").unwrap();
+    let err = schema.encode(&Json::parse(r#"{"n": "0xffffffffffffffff"}"#).unwrap()).unwrap_err().to_string();
+    assert!(err.contains("exceed the 268435456-byte limit"), "{err}");
+}
+
+#[test]
+fn encode_cli_hex_output() {
+    let dir = common::scratch("encode");
+    let schema = dir.join("msg.pakx");
+    std::fs::write(&schema, "id: u16be\nname: string[4]\n").unwrap();
+
+    let mut cmd = bin();
+    cmd.args(["encode", "--out", "hex", "--schema"]).arg(&schema);
+    cmd.write_stdin(r#"{"id": "0x0102", "name": "hi"}"#);
+    cmd.assert().success().stdout("01 02 68 69 00 00\n");
+
+    let mut bad = bin();
+    bad.args(["encode", "--schema"]).arg(&schema);
+    bad.write_stdin(r#"{"id": 1, "name": "toolong"}"#);
+    bad.assert().failure().stderr(predicate::str::contains("name: string of 7 bytes does not fit in 4"));
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,48 @@
+use pakx::cmd::bits::{pack_fields, BitOrder};
+use pakx::error::Loc;
+use pakx::util::{pack_scalar, parse_hex_str, parse_int, Endian, Width};
+use pakx::{Packer, PakxError};
+
+#[test]
+fn hex_errors_carry_location() {
+    let err = parse_hex_str("de ad\nbe eg").unwrap_err();
+    assert_eq!(
+        err,
+        PakxError::BadDigit { token: "eg".into(), loc: Loc { index: 3, offset: 9, line: 2, column: 4 } }
+    );
+    assert!(matches!(parse_hex_str("abc"), Err(PakxError::OddNibbles { .. })));
+}
+
+#[test]
+fn pack_errors_are_matchable() {
+    assert!(matches!(
+        pack_scalar(0x100, 8, Endian::Little, false, true),
+        Err(PakxError::Overflow { value: 0x100, bits: 8, signed: false, loc: None })
+    ));
+    assert!(matches!(pack_scalar(-1, 8, Endian::Little, false, true), Err(PakxError::Negative { .. })));
+    assert!(matches!(parse_int("0xzz"), Err(PakxError::BadInt { .. })));
+}
+
+#[test]
+fn packer_reports_value_index() {
+    let err = Packer::new(Width::W8).strict(true).pack(&["1", "2", "300"]).unwrap_err();
+    let err = err.downcast::<PakxError>().unwrap();
+    let loc = Loc { index: 2, offset: 4, line: 1, column: 5 };
+    assert!(matches!(err, PakxError::Overflow { loc: Some(l), .. } if l == loc));
+    assert!(err.to_string().starts_with("token 2 at line 1, column 5 (offset 4): "));
+
+    let err = Packer::new(Width::W8).pack(&["1", "0x1zz"]).unwrap_err();
+    let err = err.downcast::<PakxError>().unwrap();
+    assert!(matches!(err, PakxError::BadInt { loc: Some(Loc { index: 1, offset: 2, .. }), .. }));
+
+    let err = Packer::new(Width::W8).strict(true).pack_ints(&[1, 256]).unwrap_err();
+    assert!(err.to_string().starts_with("token 1: "));
+}
+
+#[test]
+fn bitfield_overflow_names_the_field() {
+    let err = pack_fields(&[(4, 1), (3, 8)], 8, BitOrder::Lsb0, false).unwrap_err();
+    let err = err.downcast::<PakxError>().unwrap();
+    assert!(matches!(err, PakxError::FieldOverflow { field: 1, bits: 3, .. }));
+    assert_eq!(err.to_string(), "field 1: value 8 does not fit in unsigned 3-bit");
+}
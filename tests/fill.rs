@@ -0,0 +1,52 @@
+use predicates::prelude::*;
+use pakx::util::{Endian, Width};
+use pakx::Packer;
+
+mod common;
+use common::bin;
+
+#[test]
+fn fill_to_repeats_with_partial_last_copy() {
+    let mut cmd = bin();
+    cmd.args(["p32", "--be", "0x41424344", "--fill-to", "10"]);
+    cmd.assert().success().stdout("ABCDABCDAB");
+}
+
+#[test]
+fn max_len_caps_repeats_with_a_tail() {
+    let mut cmd = bin();
+    cmd.args(["p16", "--be", "0x4142", "--repeat", "100", "--max-len", "5"]);
+    cmd.assert().success().stdout("ABABA");
+
+    // Shorter output is left alone.
+    let mut cmd = bin();
+    cmd.args(["p8", "0x41", "--max-len", "0x10"]);
+    cmd.assert().success().stdout("A");
+}
+
+#[test]
+fn layout_follows_the_cycle() {
+    let p = Packer::new(Width::W16).endian(Endian::Big).align(Some(4)).fill_to(Some(7));
+    let packed = p.pack_layout(&["0x4142"]).unwrap();
+    assert_eq!(packed.bytes, b"AB\0\0AB\0");
+    assert_eq!(packed.values, vec![(0, 0..2), (0, 4..6)]);
+    assert_eq!(packed.padding, vec![(2..4), (6..7)]);
+
+    let mut cmd = bin();
+    cmd.args(["p8", "1", "2", "--fill-to", "3", "--explain"]);
+    cmd.assert().success().stderr(predicate::str::contains("0x0002      1  value 0"));
+}
+
+#[test]
+fn fill_to_needs_values() {
+    let mut cmd = bin();
+    cmd.args(["p8", "--fill-to", "4"]);
+    cmd.assert().failure().stderr(predicate::str::contains("nothing to repeat"));
+}
+
+#[test]
+fn fill_to_is_capped() {
+    let mut cmd = bin();
+    cmd.args(["p8", "0x41", "--fill-to", "0xffffffffffff"]);
+    cmd.assert().failure().stderr(predicate::str::contains("exceeds the 268435456-byte limit"));
+}
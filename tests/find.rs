@@ -0,0 +1,54 @@
+use predicates::prelude::*;
+use pakx::cmd::find::find_all;
+
+mod common;
+use common::bin;
+
+const DUMP: &[u8] = b"\x00\xef\xbe\xad\xde\x00\x00\x00\xde\xad\xbe\xef\xef\xbe\xad\xde";
+
+#[test]
+fn finds_value_in_chosen_endianness() {
+    let mut cmd = bin();
+    cmd.args(["find", "--p32", "0xdeadbeef"]).write_stdin(DUMP);
+    cmd.assert().success().stdout("0x00000001\n0x0000000c\n");
+
+    let mut cmd = bin();
+    cmd.args(["find", "--p32", "0xdeadbeef", "--be"]).write_stdin(DUMP);
+    cmd.assert().success().stdout("0x00000008\n");
+}
+
+#[test]
+fn any_endian_labels_each_hit() {
+    let mut cmd = bin();
+    cmd.args(["find", "--p32", "0xdeadbeef", "--any-endian"]).write_stdin(DUMP);
+    cmd.assert().success().stdout("0x00000001  le\n0x00000008  be\n0x0000000c  le\n");
+}
+
+#[test]
+fn aligned_and_max_limit_hits() {
+    let mut cmd = bin();
+    cmd.args(["find", "--p32", "0xdeadbeef", "--aligned"]).write_stdin(DUMP);
+    cmd.assert().success().stdout("0x0000000c\n");
+
+    assert_eq!(find_all(DUMP, &[0], false, Some(2)), [0, 5]);
+    assert_eq!(find_all(DUMP, &[0], false, None), [0, 5, 6, 7]);
+}
+
+#[test]
+fn searches_a_file_and_rejects_missing_value() {
+    let dir = common::scratch("find");
+    let f = dir.join("dump.bin");
+    std::fs::write(&f, DUMP).unwrap();
+    let mut cmd = bin();
+    cmd.args(["find", f.to_str().unwrap(), "--p16", "-16657", "--signed", "--be"]);
+    cmd.assert().success().stdout("0x0000000a\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut cmd = bin();
+    cmd.args(["find", "--p8", "0x1ff"]).write_stdin(DUMP);
+    cmd.assert().failure().stderr(predicate::str::contains("value 511 does not fit in unsigned 8-bit"));
+
+    let mut cmd = bin();
+    cmd.args(["find"]).write_stdin(DUMP);
+    cmd.assert().failure().stderr(predicate::str::contains("required"));
+}
@@ -0,0 +1,55 @@
+use predicates::prelude::*;
+
+mod common;
+use common::bin;
+
+#[test]
+fn flat_mixes_ints_strings_blobs_and_placements() {
+    let mut cmd = bin();
+    cmd.args(["flat", "--out", "hex", "-q", "u32:0xdeadbeef", "str:AB", "hex:90 90", "@0x0c", "i8:-1"]);
+    cmd.assert().success().stdout("ef be ad de 41 42 90 90 00 00 00 00 ff\n");
+}
+
+#[test]
+fn flat_fill_and_repeat_use_filler() {
+    let mut cmd = bin();
+    cmd.args(["flat", "--be", "--filler", "0x41", "--out", "hex", "-q", "fill:3", "rep:2:u16:0x0102", "@8"]);
+    cmd.assert().success().stdout("41 41 41 01 02 01 02 41\n");
+}
+
+#[test]
+fn flat_prints_offset_map_on_stderr() {
+    let mut cmd = bin();
+    cmd.args(["flat", "--out", "hex", "u64:1", "@0x10", "str:x"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("0x0008      8  pad to @0x10"))
+        .stderr(predicate::str::contains("0x0010      1  str:x"));
+}
+
+#[test]
+fn flat_overlapping_placement_errors() {
+    let mut cmd = bin();
+    cmd.args(["flat", "u32:1", "u32:2", "@4", "u8:1"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("placement at 0x4 overlaps item 1 (u32:2)"));
+}
+
+#[test]
+fn flat_rejects_oversized_payloads_and_bad_escapes() {
+    for args in [["@0xffffffffffff"], ["fill:0x7fffffffffffffff"], ["rep:0x10000000:u32:1"]] {
+        let mut cmd = bin();
+        cmd.arg("flat").args(args);
+        cmd.assert().failure().stderr(predicate::str::contains("limit"));
+    }
+    let mut cmd = bin();
+    cmd.args(["flat", "-q", "--out", "hex", "rep:0x7fffffffffffffff:str:"]);
+    cmd.assert().failure().stderr(predicate::str::contains("limit"));
+
+    for esc in ["str:\\x4", "str:\\x+1", "str:\\x4g"] {
+        let mut cmd = bin();
+        cmd.args(["flat", esc]);
+        cmd.assert().failure().stderr(predicate::str::contains("exactly two hex digits"));
+    }
+}
@@ -0,0 +1,113 @@
+use predicates::prelude::*;
+use pakx::cmd::fmtstr::{build_fmtstr, parse_write, FmtOpts, WriteSize};
+use pakx::util::{Endian, Width};
+use std::collections::BTreeMap;
+
+mod common;
+use common::bin;
+
+/// Run the payload through a toy printf whose stack arguments start at
+/// `offset` with the payload itself, and return the bytes stored by %n.
+fn simulate(payload: &[u8], opts: &FmtOpts) -> BTreeMap<u64, u8> {
+    let slot = opts.width.bytes();
+    let arg = |i: usize| {
+        let s = &payload[(i - opts.offset) * slot..][..slot];
+        let mut v = 0u64;
+        for (k, &b) in s.iter().enumerate() {
+            let shift = match opts.endian { Endian::Little => k, Endian::Big => slot - 1 - k };
+            v |= (b as u64) << (8 * shift);
+        }
+        v
+    };
+    let fmt_end = payload.iter().position(|&b| b == 0).unwrap_or(payload.len());
+    let text = String::from_utf8_lossy(&payload[..fmt_end]);
+    let mut count = opts.written as u64;
+    let mut mem = BTreeMap::new();
+    let mut rest = text.as_ref();
+    while let Some(p) = rest.find('%') {
+        count += p as u64;
+        rest = &rest[p + 1..];
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap();
+        let n: u64 = rest[..digits].parse().unwrap();
+        rest = &rest[digits..];
+        if let Some(r) = rest.strip_prefix('c') {
+            count += n;
+            rest = r;
+            continue;
+        }
+        rest = rest.strip_prefix('$').unwrap();
+        let len = rest.find('n').unwrap();
+        let bytes = match &rest[..len] { "hh" => 1, "h" => 2, _ => 4 };
+        rest = &rest[len + 1..];
+        let addr = arg(n as usize);
+        for k in 0..bytes {
+            let shift = match opts.endian { Endian::Little => k, Endian::Big => bytes - 1 - k };
+            mem.insert(addr + k as u64, (count >> (8 * shift)) as u8);
+        }
+    }
+    mem
+}
+
+fn opts(size: WriteSize, endian: Endian) -> FmtOpts {
+    FmtOpts { offset: 6, width: Width::W64, endian, size, written: 0, addr_first: false, pad: b'a' }
+}
+
+#[test]
+fn payload_writes_the_requested_bytes() {
+    for size in [WriteSize::Byte, WriteSize::Short] {
+        for endian in [Endian::Little, Endian::Big] {
+            let o = opts(size, endian);
+            let writes = [parse_write("0x601018=0xdeadbeef").unwrap(), parse_write("0x602000=u16:0x4142").unwrap()];
+            let p = build_fmtstr(&writes, &o).unwrap();
+            let mem = simulate(&p.bytes, &o);
+            let want: Vec<u8> = match endian {
+                Endian::Little => vec![0xef, 0xbe, 0xad, 0xde, 0, 0, 0, 0],
+                Endian::Big => vec![0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef],
+            };
+            let got: Vec<u8> = (0..8).map(|k| mem[&(0x601018 + k)]).collect();
+            assert_eq!(got, want);
+            let tail = if endian == Endian::Little { [0x42, 0x41] } else { [0x41, 0x42] };
+            assert_eq!([mem[&0x602000], mem[&0x602001]], tail);
+        }
+    }
+}
+
+#[test]
+fn addresses_first_count_toward_printed_bytes() {
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "4", "--width", "32", "--addr-first", "--size", "short", "--write", "0x0804a010=0x41424344"]);
+    cmd.assert().success().stdout(&b"\x12\xa0\x04\x08\x10\xa0\x04\x08%16698c%4$hn%514c%5$hn"[..]);
+}
+
+#[test]
+fn explain_maps_format_padding_and_addresses() {
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "6", "--write", "0x601018=u16:0x4142", "--explain", "--out", "hex"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("0x0000     20  format"))
+        .stderr(predicate::str::contains("0x0014      4  padding"))
+        .stderr(predicate::str::contains("0x0020      8  address of write 0 (0x601018)"));
+}
+
+#[test]
+fn padding_avoids_bad_characters() {
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "6", "--write", "0x601018=u16:0x4142", "--badchars", "61", "--out", "hex"]);
+    cmd.assert().success().stdout(predicate::str::contains("62 62 62 62 19 10 60"));
+
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "6", "--write", "0x601018=1", "--badchars", "00"]);
+    cmd.assert().failure().stderr(predicate::str::contains("from address of write 0"));
+}
+
+#[test]
+fn rejects_malformed_writes() {
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "6", "--write", "0x601018"]);
+    cmd.assert().failure().stderr(predicate::str::contains("write 0 (0x601018): expected ADDR=VALUE"));
+
+    let mut cmd = bin();
+    cmd.args(["fmtstr", "--offset", "6", "--size", "short", "--write", "0x10=u8:1"]);
+    cmd.assert().failure().stderr(predicate::str::contains("cannot be split into 2-byte writes"));
+}
@@ -0,0 +1,56 @@
+use predicates::prelude::*;
+
+mod common;
+use common::bin;
+
+#[test]
+fn shows_bases_bytes_and_ascii() {
+    let mut cmd = bin();
+    cmd.args(["info", "0x41424344"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("dec       1094861636\n"))
+        .stdout(predicate::str::contains("oct       0o10120441504\n"))
+        .stdout(predicate::str::contains("bin       0b1000001010000100100001101000100\n"))
+        .stdout(predicate::str::contains("le        44 43 42 41\n"))
+        .stdout(predicate::str::contains("be        41 42 43 44\n"))
+        .stdout(predicate::str::contains("ascii     le \"DCBA\"  be \"ABCD\""));
+}
+
+#[test]
+fn shows_float_popcount_and_bitlen() {
+    let mut cmd = bin();
+    cmd.args(["info", "0x3ff0000000000000"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("f64       1.0\n"))
+        .stdout(predicate::str::contains("popcount  10\n"))
+        .stdout(predicate::str::contains("bitlen    62\n"));
+
+    let mut cmd = bin();
+    cmd.args(["info", "0x3fc00000"]);
+    cmd.assert().success().stdout(predicate::str::contains("f32       1.5\n"));
+}
+
+#[test]
+fn negative_values_show_both_signednesses() {
+    let mut cmd = bin();
+    cmd.args(["info", "-1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hex       0xffffffffffffffff\n"))
+        .stdout(predicate::str::contains("le        ff\n"))
+        .stdout(predicate::str::contains("8      255                     -1\n"))
+        .stdout(predicate::str::contains("64     18446744073709551615    -1\n"));
+}
+
+#[test]
+fn narrow_widths_truncate() {
+    let mut cmd = bin();
+    cmd.args(["info", "0x1ff"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("le        ff 01\n"))
+        .stdout(predicate::str::contains("8      255                     -1\n"))
+        .stdout(predicate::str::contains("16     511                     511\n"));
+}
@@ -0,0 +1,50 @@
+use predicates::prelude::*;
+use pakx::cmd::pad::{pad_bytes, Justify, PadOpts};
+
+mod common;
+use common::bin;
+
+#[test]
+fn pads_to_exact_size_with_byte() {
+    let mut cmd = bin();
+    cmd.args(["pad", "--to", "4", "--with", "0x90", "--out", "hex"]).write_stdin("AB");
+    cmd.assert().success().stdout("41 42 90 90\n");
+}
+
+#[test]
+fn rjust_puts_padding_first() {
+    let mut cmd = bin();
+    cmd.args(["pad", "--in", "hex", "--to", "2*2", "--rjust", "--out", "hex"]).write_stdin("41 42");
+    cmd.assert().success().stdout("00 00 41 42\n");
+}
+
+#[test]
+fn align_rounds_size_up() {
+    let opts = PadOpts { align: Some(4), ..Default::default() };
+    assert_eq!(pad_bytes(b"ABCDE", &opts).unwrap(), b"ABCDE\0\0\0");
+    assert_eq!(pad_bytes(b"ABCD", &opts).unwrap(), b"ABCD");
+    let opts = PadOpts { to: Some(5), align: Some(4), with: b'.', justify: Justify::Right, truncate: false };
+    assert_eq!(pad_bytes(b"AB", &opts).unwrap(), b"......AB");
+    assert!(pad_bytes(b"AB", &PadOpts { align: Some(0), ..Default::default() }).is_err());
+}
+
+#[test]
+fn huge_sizes_are_errors() {
+    let opts = PadOpts { to: Some(usize::MAX - 1), align: Some(4), ..Default::default() };
+    assert!(pad_bytes(b"AB", &opts).unwrap_err().to_string().contains("overflows"));
+
+    let mut cmd = bin();
+    cmd.args(["pad", "--to", "0xffffffffffff"]).write_stdin("AB");
+    cmd.assert().failure().stderr(predicate::str::contains("exceeds the 268435456-byte limit"));
+}
+
+#[test]
+fn too_long_input_fails_unless_truncated() {
+    let mut cmd = bin();
+    cmd.args(["pad", "--to", "3"]).write_stdin("ABCDE");
+    cmd.assert().failure().stderr(predicate::str::contains("input is 5 bytes, longer than --to 3"));
+
+    let mut cmd = bin();
+    cmd.args(["pad", "--to", "3", "--truncate"]).write_stdin("ABCDE");
+    cmd.assert().success().stdout("ABC");
+}
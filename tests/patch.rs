@@ -0,0 +1,107 @@
+use predicates::prelude::*;
+use std::path::PathBuf;
+
+mod common;
+use common::bin;
+
+fn scratch(name: &str) -> PathBuf {
+    let f = common::scratch(&format!("patch-{name}")).join("fw.bin");
+    std::fs::write(&f, [0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+    f
+}
+
+#[test]
+fn patches_packed_value_in_place() {
+    let f = scratch("inplace");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "2", "--p32", "0xdeadbeef", "--be"]);
+    cmd.assert().success();
+    assert_eq!(std::fs::read(&f).unwrap(), [0, 1, 0xde, 0xad, 0xbe, 0xef, 6, 7]);
+    std::fs::remove_dir_all(f.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn output_copy_leaves_original_untouched() {
+    let f = scratch("copy");
+    let out = f.with_file_name("copy.bin");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "6", "--bytes", "aa bb", "-o", out.to_str().unwrap()]);
+    cmd.assert().success();
+    assert_eq!(std::fs::read(&f).unwrap(), [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(std::fs::read(&out).unwrap(), [0, 1, 2, 3, 4, 5, 0xaa, 0xbb]);
+
+    let src = f.with_file_name("new.bin");
+    std::fs::write(&src, [9, 9]).unwrap();
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "0", "--from-file", src.to_str().unwrap()]);
+    cmd.assert().success();
+    assert_eq!(&std::fs::read(&f).unwrap()[..3], [9, 9, 2]);
+    std::fs::remove_dir_all(f.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn output_naming_the_input_patches_in_place() {
+    let f = scratch("same");
+    let dir = f.parent().unwrap();
+    let alias = dir.join(".").join("fw.bin");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "1", "--bytes", "aa", "-o", alias.to_str().unwrap()]);
+    cmd.assert().success();
+    assert_eq!(std::fs::read(&f).unwrap(), [0, 0xaa, 2, 3, 4, 5, 6, 7]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn expect_guard_refuses_mismatch() {
+    let f = scratch("expect");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "1", "--p8", "0xff", "--expect", "01 03"]);
+    cmd.assert().failure().stderr(predicate::str::contains("expected 01 03 at 0x1, found 01 02"));
+    assert_eq!(std::fs::read(&f).unwrap(), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "1", "--p8", "0xff", "--expect", "01 02"]);
+    cmd.assert().success();
+    assert_eq!(&std::fs::read(&f).unwrap()[..3], [0, 0xff, 2]);
+    std::fs::remove_dir_all(f.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn values_follow_pack_rules() {
+    let f = scratch("rules");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "0", "--p16", "-2", "--signed"]);
+    cmd.assert().success();
+    assert_eq!(&std::fs::read(&f).unwrap()[..2], [0xfe, 0xff]);
+
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "0", "--p8", "300", "--strict"]);
+    cmd.assert().failure().stderr(predicate::str::contains("does not fit in unsigned 8-bit"));
+    std::fs::remove_dir_all(f.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn refuses_to_grow_the_file() {
+    let f = scratch("grow");
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "6", "--p32", "1"]);
+    cmd.assert().failure().stderr(predicate::str::contains("runs past the end"));
+    assert_eq!(std::fs::read(&f).unwrap().len(), 8);
+
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "0xffffffffffffffff", "--p8", "1"]);
+    cmd.assert().failure().stderr(predicate::str::contains("runs past the end"));
+    std::fs::remove_dir_all(f.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn checks_new_bytes_against_badchars() {
+    let dir = common::scratch("patch-badchars");
+    let f = dir.join("fw.bin");
+    std::fs::write(&f, [0x41; 8]).unwrap();
+    let mut cmd = bin();
+    cmd.args(["patch", f.to_str().unwrap(), "--at", "4", "--p16", "0x0a42", "--badchars", "0a"]);
+    cmd.assert().failure().stderr(predicate::str::contains("offset 0x0001: 0x0a from file offset 0x5"));
+    assert_eq!(std::fs::read(&f).unwrap(), [0x41; 8]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,5 +1,5 @@
 use proptest::prelude::*;
-use pakx::util::{pack_scalar, unpack_scalar, Endian};
+use pakx::util::{pack_scalar, try_pack, try_unpack, unpack_scalar, Endian, Width};
 
 fn mask(bits: u32, x: i128) -> i128 {
     if bits == 128 { x } else {
@@ -15,7 +15,7 @@ proptest! {
                           x in any::<i128>()) {
         let endian = if be { Endian::Big } else { Endian::Little };
         let b = pack_scalar(x, width, endian, /*signed*/ false, /*strict*/ false).unwrap();
-        let y = unpack_scalar(&b, width, endian, /*signed*/ false);
+        let y = unpack_scalar(&b, width, endian, /*signed*/ false).unwrap();
         prop_assert_eq!(y, mask(width, x));
     }
 
@@ -30,7 +30,49 @@ proptest! {
 
         let endian = if be { Endian::Big } else { Endian::Little };
         let b = pack_scalar(xi, width, endian, /*signed*/ true, /*strict*/ true).unwrap();
-        let y = unpack_scalar(&b, width, endian, /*signed*/ true);
+        let y = unpack_scalar(&b, width, endian, /*signed*/ true).unwrap();
         prop_assert_eq!(y, xi);
     }
+
+    #[test]
+    fn try_roundtrip_signed_full_range(width in prop_oneof![Just(8u32), Just(16), Just(32), Just(64), Just(128)],
+                                       be in any::<bool>(),
+                                       x in any::<i128>()) {
+        let w = Width::new(width).unwrap();
+        let xi = if width == 128 { x } else {
+            let half = 1i128 << (width - 1);
+            x.rem_euclid(2 * half) - half
+        };
+
+        let endian = if be { Endian::Big } else { Endian::Little };
+        let b = try_pack(xi, w, endian, /*signed*/ true, /*strict*/ true).unwrap();
+        let y = try_unpack(&b, w, endian, /*signed*/ true).unwrap();
+        prop_assert_eq!(y, xi);
+    }
+
+    #[test]
+    fn try_unpack_short_input_errors(width in prop_oneof![Just(16u32), Just(32), Just(64), Just(128)],
+                                     len in 0usize..16) {
+        let w = Width::new(width).unwrap();
+        prop_assume!(len < w.bytes());
+        prop_assert!(try_unpack(&vec![0u8; len], w, Endian::Little, false).is_err());
+    }
+}
+
+#[test]
+fn width_rejects_unsupported() {
+    assert!(Width::new(24).is_err());
+    assert!(pack_scalar(1, 24, Endian::Little, false, false).is_err());
+    assert!(unpack_scalar(&[0; 3], 24, Endian::Little, false).is_err());
+    assert_eq!("24".parse::<Width>().unwrap_err().to_string(), "unsupported width 24: must be one of 8, 16, 32, 64, 128");
+    assert_eq!("x8".parse::<Width>().unwrap_err().to_string(), "invalid width \"x8\": must be one of 8, 16, 32, 64, 128");
+    assert_eq!(Width::new(128).unwrap().bytes(), 16);
+}
+
+#[test]
+fn signed_128_extremes() {
+    for x in [i128::MIN, -1, 0, i128::MAX] {
+        let b = try_pack(x, Width::W128, Endian::Little, true, true).unwrap();
+        assert_eq!(try_unpack(&b, Width::W128, Endian::Little, true).unwrap(), x);
+    }
 }
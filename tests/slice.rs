@@ -0,0 +1,51 @@
+use predicates::prelude::*;
+use pakx::cmd::slice::{resolve_range, Span};
+use pakx::util::parse_expr;
+
+mod common;
+use common::bin;
+
+const DATA: &str = "0123456789abcdef";
+
+#[test]
+fn expressions_follow_precedence() {
+    assert_eq!(parse_expr("0x200+0x40").unwrap(), 0x240);
+    assert_eq!(parse_expr("0x1000 - 4*0x10").unwrap(), 0xfc0);
+    assert_eq!(parse_expr("-0x10").unwrap(), -16);
+    assert_eq!(parse_expr("2*-3").unwrap(), -6);
+    assert!(parse_expr("0x10+").is_err());
+    assert!(parse_expr("1 2").is_err());
+    assert!(parse_expr("--4").is_err());
+    assert_eq!(parse_expr("2 - -3").unwrap(), 5);
+}
+
+#[test]
+fn slice_by_offset_and_len() {
+    let mut cmd = bin();
+    cmd.args(["slice", "--offset", "0x2+2", "--len", "2*2"]).write_stdin(DATA);
+    cmd.assert().success().stdout("4567");
+}
+
+#[test]
+fn negative_offsets_count_from_eof() {
+    let mut cmd = bin();
+    cmd.args(["slice", "--offset", "-4", "--out", "hex"]).write_stdin(DATA);
+    cmd.assert().success().stdout("63 64 65 66\n");
+
+    let mut cmd = bin();
+    cmd.args(["slice", "--offset", "1", "--end", "-1"]).write_stdin(DATA);
+    cmd.assert().success().stdout("123456789abcde");
+    assert_eq!(resolve_range(16, 0, Span::ToEof).unwrap(), 0..16);
+}
+
+#[test]
+fn out_of_range_is_an_error() {
+    let mut cmd = bin();
+    cmd.args(["slice", "--offset", "1", "--len", "0x20"]).write_stdin(DATA);
+    cmd.assert().failure().stderr(predicate::str::contains("range 0x1..0x21 runs past the end of the input (16 bytes)"));
+
+    assert!(resolve_range(16, -17, Span::ToEof).is_err());
+    let err = resolve_range(16, 1, Span::Len(i128::MAX)).unwrap_err().to_string();
+    assert!(err.contains("runs past the end of the input"), "{err}");
+    assert!(resolve_range(16, 8, Span::End(4)).is_err());
+}
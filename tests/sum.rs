@@ -0,0 +1,72 @@
+use predicates::prelude::*;
+use pakx::checksum::{Algo, CrcParams};
+
+mod common;
+use common::bin;
+
+#[test]
+fn catalogue_check_values() {
+    let check = b"123456789";
+    let want = [
+        ("crc8", 0xf4),
+        ("crc16-ccitt", 0x29b1),
+        ("crc16-modbus", 0x4b37),
+        ("crc32", 0xcbf4_3926),
+        ("crc32c", 0xe306_9283),
+        ("adler32", 0x091e_01de),
+    ];
+    for (name, v) in want {
+        assert_eq!(Algo::named(name).unwrap().compute(check), v, "{name}");
+    }
+    assert_eq!(Algo::Fletcher16.compute(b"abcde"), 0xc8f0);
+    assert_eq!(Algo::Fletcher32.compute(b"abcde"), 0xf04f_c729);
+}
+
+#[test]
+fn inet_checksum_of_ipv4_header() {
+    // Example header from RFC 1071 discussions; the checksum field is zeroed.
+    let hdr = [0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7];
+    assert_eq!(Algo::Inet.compute(&hdr), 0xb861);
+}
+
+#[test]
+fn sum_packs_with_endianness_and_format() {
+    let mut cmd = bin();
+    cmd.args(["sum", "--algo", "crc32"]).write_stdin("123456789");
+    cmd.assert().success().stdout("26 39 f4 cb\n");
+
+    let mut cmd = bin();
+    cmd.args(["sum", "--algo", "crc32", "--be", "--in", "hex", "--out", "c"]).write_stdin("31 32 33 34 35 36 37 38 39");
+    cmd.assert().success().stdout(predicate::str::starts_with("\\xcb\\xf4\\x39\\x26"));
+}
+
+#[test]
+fn custom_crc_parameters() {
+    // CRC-16/KERMIT, and CRC-24/OPENPGP which is not a whole number of words.
+    let mut cmd = bin();
+    cmd.args(["sum", "--crc-width", "16", "--poly", "0x1021", "--refin", "true", "--refout", "true", "--be"]).write_stdin("123456789");
+    cmd.assert().success().stdout("21 89\n");
+
+    let mut cmd = bin();
+    cmd.args(["sum", "--crc-width", "24", "--poly", "0x864cfb", "--init", "0xb704ce", "--be"]).write_stdin("123456789");
+    cmd.assert().success().stdout("21 cf 02\n");
+
+    // Overriding a catalogued CRC: crc32 without the final xor is CRC-32/JAMCRC.
+    let mut cmd = bin();
+    cmd.args(["sum", "--algo", "crc32", "--xorout", "0", "--be"]).write_stdin("123456789");
+    cmd.assert().success().stdout("34 0b c6 d9\n");
+}
+
+#[test]
+fn rejects_bad_parameters() {
+    let bad = CrcParams { width: 8, poly: 0x107, ..CrcParams::CRC8 };
+    assert!(bad.validate().is_err());
+
+    let mut cmd = bin();
+    cmd.args(["sum", "--algo", "md5"]).write_stdin("");
+    cmd.assert().failure().stderr(predicate::str::contains("unknown algorithm md5"));
+
+    let mut cmd = bin();
+    cmd.args(["sum", "--algo", "adler32", "--poly", "1"]).write_stdin("");
+    cmd.assert().failure().stderr(predicate::str::contains("CRC parameters only apply to CRC algorithms"));
+}
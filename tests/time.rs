@@ -0,0 +1,82 @@
+use predicates::prelude::*;
+use pakx::time::{civil_from_days, days_from_civil, TimeFormat, Timestamp};
+use proptest::prelude::*;
+
+mod common;
+use common::bin;
+
+#[test]
+fn packs_every_format() {
+    let want = [
+        ("unix32", "66 32 2e c0\n"),
+        ("unix64", "00 00 00 00 66 32 2e c0\n"),
+        ("unix-ms", "00 00 01 8f 34 06 9e 00\n"),
+        ("filetime", "01 da 9b bf 17 ba 60 00\n"),
+        ("dosdate", "58 a1 60 00\n"),
+        ("hfs", "e2 57 df 40\n"),
+    ];
+    for (format, hex) in want {
+        let mut cmd = bin();
+        cmd.args(["time", "pack", "2024-05-01T12:00:00Z", "--format", format, "--be", "--out", "hex"]);
+        cmd.assert().success().stdout(hex);
+    }
+}
+
+#[test]
+fn unpack_applies_offsets_and_keeps_fractions() {
+    let mut cmd = bin();
+    cmd.args(["time", "unpack", "--format", "filetime", "--in", "hex"]).write_stdin("00 60 ba 17 bf 9b da 01");
+    cmd.assert().success().stdout("2024-05-01T12:00:00Z\n");
+
+    let ts = Timestamp::parse("2024-05-01 14:00:01.25+02:00").unwrap();
+    assert_eq!(ts.to_iso(), "2024-05-01T12:00:01.25Z");
+    let ms = TimeFormat::UnixMs.encode(Timestamp::parse("1969-12-31T23:59:59.999Z").unwrap()).unwrap();
+    assert_eq!(ms, -1);
+    assert_eq!(TimeFormat::UnixMs.decode(ms).unwrap().to_iso(), "1969-12-31T23:59:59.999Z");
+}
+
+#[test]
+fn dosdate_rounds_to_two_seconds() {
+    let ts = Timestamp::parse("2024-05-01T12:00:03Z").unwrap();
+    let v = TimeFormat::DosDate.encode(ts).unwrap();
+    assert_eq!(TimeFormat::DosDate.decode(v).unwrap().to_iso(), "2024-05-01T12:00:02Z");
+    assert!(TimeFormat::DosDate.decode(0).is_err());
+}
+
+#[test]
+fn rejects_bad_dates_and_ranges() {
+    let mut cmd = bin();
+    cmd.args(["time", "pack", "2024-02-30", "--format", "unix32"]);
+    cmd.assert().failure().stderr(predicate::str::contains("no such date"));
+
+    let mut cmd = bin();
+    cmd.args(["time", "pack", "2040-01-01", "--format", "unix32"]);
+    cmd.assert().failure().stderr(predicate::str::contains("outside the range of unix32"));
+
+    let mut cmd = bin();
+    cmd.args(["time", "pack", "1979-12-31", "--format", "dosdate"]);
+    cmd.assert().failure().stderr(predicate::str::contains("outside the dosdate range"));
+
+    assert!(Timestamp::parse("2024-5-1").is_err());
+    assert!(Timestamp::parse("2024-05-01T25:00").is_err());
+    assert!(Timestamp::parse("2024-05-01T12:00:00.Z").is_err());
+    assert!(Timestamp::parse("2024-05-01T12:00:00+0:530").is_err());
+    assert!(Timestamp::parse("2024-05-01T12:00:00+05:3").is_err());
+    assert_eq!(Timestamp::parse("2024-05-01T12:00:00+0530").unwrap(), Timestamp::parse("2024-05-01T06:30:00Z").unwrap());
+
+    let mut cmd = bin();
+    cmd.args(["time", "pack", "2024-05-01T12:00:00+24:00", "--format", "unix32"]);
+    cmd.assert().failure().stderr(predicate::str::contains("UTC offset out of range"));
+
+    let mut cmd = bin();
+    cmd.args(["time", "pack", "2024-05-01T12:00:00-05:60", "--format", "unix32"]);
+    cmd.assert().failure().stderr(predicate::str::contains("UTC offset out of range"));
+}
+
+proptest! {
+    #[test]
+    fn civil_days_roundtrip(days in -1_000_000i64..1_000_000) {
+        let (y, m, d) = civil_from_days(days);
+        prop_assert_eq!(days_from_civil(y, m, d), days);
+    }
+}
@@ -0,0 +1,57 @@
+use predicates::prelude::*;
+use pakx::cmd::uuid::{Layout, Uuid};
+
+mod common;
+use common::bin;
+
+const GUID: &str = "00112233-4455-6677-8899-aabbccddeeff";
+
+#[test]
+fn packs_both_layouts() {
+    let mut cmd = bin();
+    cmd.args(["uuid", "pack", GUID, "--out", "hex"]);
+    cmd.assert().success().stdout("00 11 22 33 44 55 66 77 88 99 aa bb cc dd ee ff\n");
+
+    let mut cmd = bin();
+    cmd.args(["uuid", "pack", GUID, "--layout", "ms", "--out", "hex"]);
+    cmd.assert().success().stdout("33 22 11 00 55 44 77 66 88 99 aa bb cc dd ee ff\n");
+}
+
+#[test]
+fn unpack_reports_version_and_variant() {
+    let mut cmd = bin();
+    cmd.args(["uuid", "unpack", "--layout", "ms", "--in", "hex"])
+        .write_stdin("00 84 0e 55 9b e2 d4 41 a7 16 44 66 55 44 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+    cmd.assert().success().stdout(
+        "550e8400-e29b-41d4-a716-446655440000  version 4, variant RFC 4122\n\
+         00000000-0000-0000-0000-000000000000  variant NCS\n",
+    );
+
+    let mut cmd = bin();
+    cmd.args(["uuid", "pack", "urn:uuid:550e8400-e29b-41d4-a716-446655440000", "--explain", "--out", "hex"]);
+    cmd.assert().success().stderr("value 0: 550e8400-e29b-41d4-a716-446655440000  version 4, variant RFC 4122\n");
+}
+
+#[test]
+fn layouts_round_trip() {
+    for text in [GUID, "{6BA7B810-9DAD-11D1-80B4-00C04FD430C8}", "6ba7b8109dad11d180b400c04fd430c8"] {
+        let u = Uuid::parse(text).unwrap();
+        for layout in [Layout::Rfc, Layout::Ms] {
+            assert_eq!(Uuid::from_bytes(u.to_bytes(layout), layout), u);
+        }
+    }
+    let u = Uuid::parse("6BA7B810-9DAD-11D1-80B4-00C04FD430C8").unwrap();
+    assert_eq!(u.to_string(), "6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+    assert_eq!(u.version(), Some(1));
+}
+
+#[test]
+fn rejects_malformed_input() {
+    let mut cmd = bin();
+    cmd.args(["uuid", "pack", "00112233-44556677-8899-aabbccddeeff0"]);
+    cmd.assert().failure().stderr(predicate::str::contains("invalid UUID"));
+
+    let mut cmd = bin();
+    cmd.args(["uuid", "unpack", "--in", "hex"]).write_stdin("00 11 22");
+    cmd.assert().failure().stderr(predicate::str::contains("need 16 bytes, got 3"));
+}